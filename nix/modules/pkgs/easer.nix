@@ -1,9 +1,11 @@
 {
   pkgs,
   lib,
+  stdenv,
   rustPlatform,
   pkg-config,
   openssl,
+  installShellFiles,
   rustPackages,
   ...
 }:
@@ -27,8 +29,19 @@ rustPlatform.buildRustPackage {
   PKG_CONFIG_PATH = "${pkgs.openssl.dev}/lib/pkgconfig";
   nativeBuildInputs = [
     pkg-config
+    installShellFiles
   ];
   buildInputs = [
     openssl
   ];
+  # The completion scripts call back into easer for candidates, so they are
+  # generated by the binary itself
+  postInstall = lib.optionalString (stdenv.buildPlatform.canExecute stdenv.hostPlatform) ''
+    installShellCompletion --cmd easer \
+      --bash <($out/bin/easer completions bash) \
+      --fish <($out/bin/easer completions fish) \
+      --zsh <($out/bin/easer completions zsh)
+    $out/bin/easer man --out-dir man
+    installManPage man/*.1
+  '';
 }
@@ -1,38 +1,494 @@
-use clap::Parser;
+use crate::completions::complete_previous_tag;
+use crate::logging::LogFormat;
+use clap::{ArgAction, Parser, Subcommand};
+use clap_complete::{ArgValueCompleter, Shell};
+use easer::backend::{Forge, RepoRef};
+use easer::ci::DEFAULT_DOTENV;
+use easer::conventional::{default_sections, Section};
+use easer::http::HttpConfig;
+use easer::output::OutputFormat;
+use easer::sign::SignMethod;
+use easer::token::Token;
+use std::time::Duration;
 
+/// Top-level command line. Without a subcommand the release options create a
+/// release, as they did before subcommands existed
+#[derive(Parser, Debug)]
+#[clap(
+    name = "easer",
+    version,
+    about = "Create releases on Gitee, GitHub, Gitea/Forgejo and GitLab",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    #[clap(flatten)]
+    pub release: Args,
+    #[clap(flatten)]
+    pub log: LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Create a release and upload its artifacts (the default)
+    Release(Args),
+    /// Print the release notes generated from Conventional Commits
+    Changelog(Args),
+    /// Print the version the next release would get, offline
+    NextVersion(NextVersionArgs),
+    /// Upload files to an existing release
+    Upload(UploadArgs),
+    /// Run the pre-flight checks without creating anything
+    Check(Args),
+    /// Check that commits since the last release follow Conventional Commits
+    Lint(Args),
+    /// Verify a downloaded release's assets against its checksums and signatures
+    Verify(VerifyArgs),
+    /// Store a verified access token under a named profile
+    Login(LoginArgs),
+    /// Copy releases missing on one forge from another, including their assets
+    Mirror(MirrorArgs),
+    /// Inspect the layered configuration
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+    /// Print the shell script that registers completions for easer
+    Completions(CompletionsArgs),
+    /// Generate the man pages
+    Man(ManArgs),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the merged settings and where each value came from
+    Show(Args),
+}
+
+/// Options shared by every command that works on a repository and its releases
 #[derive(Parser, Debug, Clone)]
-#[clap(about = "Gitee release CLI tool for creating releases")]
 pub struct Args {
-    #[clap(long, help = "Repository owner")]
-    pub owner: String,
-    #[clap(long, help = "Repository name")]
-    pub repo: String,
-    #[clap(long, help = "Gitee personal access token")]
-    pub token: String,
+    #[clap(
+        long,
+        env = "EASER_OWNER",
+        help = "Repository owner (default: from the origin remote, not needed when the config file lists targets)"
+    )]
+    pub owner: Option<String>,
+    #[clap(
+        long,
+        env = "EASER_REPO",
+        help = "Repository name (default: from the origin remote)"
+    )]
+    pub repo: Option<String>,
+    #[clap(
+        long,
+        value_enum,
+        default_value = "gitee",
+        env = "EASER_FORGE",
+        help = "Forge to publish the release to"
+    )]
+    pub forge: Forge,
+    #[clap(
+        long,
+        help = "Personal access token (prefer GITEE_TOKEN/GITHUB_TOKEN or --token-file, argv is visible to other users)"
+    )]
+    pub token: Option<Token>,
+    #[clap(long, help = "Read the access token from this file")]
+    pub token_file: Option<String>,
+    #[clap(
+        long,
+        env = "EASER_API_URL",
+        help = "API base URL for Gitee Enterprise, GitHub Enterprise or self-hosted instances (default: the forge's public API)"
+    )]
+    pub api_url: Option<String>,
+    #[clap(
+        long,
+        env = "EASER_PROFILE",
+        help = "Use the token and API URL stored by `easer login` under this profile"
+    )]
+    pub profile: Option<String>,
     #[clap(long, default_value = ".", help = "Path to repository to analyze")]
     pub repo_path: String,
-    #[clap(long, help = "Previous tag to start analysis from")]
+    #[clap(
+        long,
+        add = ArgValueCompleter::new(complete_previous_tag),
+        help = "Previous tag to start analysis from"
+    )]
     pub previous_tag: Option<String>,
     #[clap(long, help = "Tag name (e.g., v1.0.0), optional for auto-generation")]
     pub tag_name: Option<String>,
+    #[clap(
+        long,
+        default_value = "v",
+        env = "EASER_TAG_PREFIX",
+        help = "Prefix of version tags (e.g., v for v1.2.3, an empty string for 1.2.3)"
+    )]
+    pub tag_prefix: String,
     #[clap(long, help = "Release name, optional for auto-generation")]
     pub name: Option<String>,
     #[clap(long, help = "Release description, optional for auto-generation")]
     pub body: Option<String>,
-    #[clap(long, help = "Target commit or branch (e.g., main)")]
-    pub target_commitish: String,
+    #[clap(
+        long,
+        env = "EASER_TARGET_COMMITISH",
+        help = "Target commit or branch (default: the current branch, or HEAD's SHA when detached)"
+    )]
+    pub target_commitish: Option<String>,
     #[clap(long, default_value = "false", help = "Is draft release")]
     pub draft: bool,
     #[clap(long, default_value = "false", help = "Is prerelease")]
     pub prerelease: bool,
-    #[clap(long, default_value = "zh-cn", value_parser = clap::value_parser!(String), help = "Language for messages (e.g., en-us, zh-cn)")]
+    #[clap(long, default_value = "zh-cn", env = "EASER_LANG", value_parser = clap::value_parser!(String), help = "Language for messages (e.g., en-us, zh-cn)")]
     pub lang: String,
-    #[clap(long, help = "Paths to asset files to upload", value_delimiter = ',')]
+    #[clap(
+        long,
+        env = "EASER_ARTIFACTS",
+        help = "Paths or glob patterns of asset files to upload",
+        value_delimiter = ','
+    )]
     pub artifacts: Option<Vec<String>>,
     #[clap(
         long,
         default_value = "false",
+        env = "EASER_AUTO_GEN_NOTES",
         help = "Is automatic generation of release notes"
     )]
     pub auto_gen_notes: bool,
+    #[clap(
+        long,
+        help = "Do not generate release notes, even if a config file or EASER_AUTO_GEN_NOTES enables it"
+    )]
+    pub no_auto_gen_notes: bool,
+    #[clap(
+        long,
+        default_value = "false",
+        env = "EASER_CHECKSUMS",
+        help = "Compute SHA-256/SHA-512 digests and upload a SHA256SUMS manifest"
+    )]
+    pub checksums: bool,
+    #[clap(
+        long,
+        help = "Do not compute checksums, even if a config file or EASER_CHECKSUMS enables them"
+    )]
+    pub no_checksums: bool,
+    #[clap(
+        long,
+        value_enum,
+        env = "EASER_SIGN",
+        help = "Sign every uploaded artifact and the checksum manifest"
+    )]
+    pub sign: Option<SignMethod>,
+    #[clap(
+        long,
+        env = "EASER_SIGN_KEY",
+        help = "Signing key: GPG key id, SSH private key file or minisign secret key file"
+    )]
+    pub sign_key: Option<String>,
+    #[clap(
+        long,
+        default_value = "false",
+        help = "Skip pre-flight validation before creating the release"
+    )]
+    pub no_preflight: bool,
+    #[clap(
+        long,
+        default_value = "false",
+        help = "Print the planned API calls without contacting the forge"
+    )]
+    pub dry_run: bool,
+    #[clap(
+        long,
+        default_value = "false",
+        help = "Review the generated version, title and notes, edit them in $EDITOR or change the bump before publishing"
+    )]
+    pub interactive: bool,
+    #[clap(
+        long,
+        env = "EASER_CONFIG",
+        help = "Project config file with defaults and release targets (default: .easer.toml in the repository)"
+    )]
+    pub config: Option<String>,
+    #[clap(
+        long,
+        default_value = "false",
+        help = "Delete the releases already created when publishing to another target fails"
+    )]
+    pub all_or_nothing: bool,
+    #[clap(
+        long,
+        value_enum,
+        default_value = "text",
+        env = "EASER_OUTPUT",
+        help = "Print the result as text or as one JSON document on stdout"
+    )]
+    pub output: OutputFormat,
+    #[clap(
+        long,
+        default_value = "false",
+        env = "EASER_NO_CI_OUTPUTS",
+        help = "Do not write step outputs and a job summary when running in GitHub Actions, Gitee Go or GitLab CI"
+    )]
+    pub no_ci_outputs: bool,
+    #[clap(
+        long,
+        default_value = DEFAULT_DOTENV,
+        env = "EASER_DOTENV",
+        help = "Dotenv report file the tag, version and release URL are written to under GitLab CI; easer-summary.md goes next to it"
+    )]
+    pub dotenv: String,
+    /// Release-note sections, only configurable in a config file
+    #[clap(skip = default_sections())]
+    pub sections: Vec<Section>,
+    /// `--owner` or `--repo` was given on the command line, which is the only
+    /// way to release to one repository instead of the config's `[[targets]]`
+    #[clap(skip)]
+    pub repo_from_command_line: bool,
+    #[clap(flatten)]
+    pub http: HttpArgs,
+}
+
+/// Logging options, accepted before or after the subcommand
+#[derive(clap::Args, Debug, Clone)]
+pub struct LogArgs {
+    #[clap(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Log more: -v for debug (including HTTP responses), -vv for trace"
+    )]
+    pub verbose: u8,
+    #[clap(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        conflicts_with = "verbose",
+        help = "Log less: -q for warnings and errors only, -qq for errors only"
+    )]
+    pub quiet: u8,
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        default_value = "text",
+        env = "EASER_LOG_FORMAT",
+        help = "Write log lines as text or as one JSON object per line"
+    )]
+    pub log_format: LogFormat,
+}
+
+/// HTTP client options shared by every command that talks to an API
+#[derive(clap::Args, Debug, Clone)]
+pub struct HttpArgs {
+    #[clap(
+        long,
+        env = "EASER_PROXY",
+        help = "HTTP(S) proxy for API requests (e.g., http://proxy:3128)"
+    )]
+    pub proxy: Option<String>,
+    #[clap(
+        long,
+        env = "EASER_CA_CERT",
+        help = "PEM file with extra CA certificates to trust"
+    )]
+    pub ca_cert: Option<String>,
+    #[clap(long, default_value = "10", help = "Connect timeout in seconds")]
+    pub connect_timeout: u64,
+    #[clap(long, default_value = "60", help = "API request timeout in seconds")]
+    pub timeout: u64,
+    #[clap(
+        long,
+        default_value = "600",
+        help = "Artifact upload timeout in seconds"
+    )]
+    pub upload_timeout: u64,
+}
+
+impl Default for HttpArgs {
+    fn default() -> Self {
+        HttpArgs {
+            proxy: None,
+            ca_cert: None,
+            connect_timeout: 10,
+            timeout: 60,
+            upload_timeout: 600,
+        }
+    }
+}
+
+impl From<&HttpArgs> for HttpConfig {
+    fn from(args: &HttpArgs) -> Self {
+        HttpConfig {
+            proxy: args.proxy.clone(),
+            ca_cert: args.ca_cert.clone(),
+            connect_timeout: Duration::from_secs(args.connect_timeout),
+            timeout: Duration::from_secs(args.timeout),
+            upload_timeout: Duration::from_secs(args.upload_timeout),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct UploadArgs {
+    #[clap(help = "Tag of the existing release")]
+    pub tag: String,
+    #[clap(
+        required = true,
+        help = "Files or glob patterns to upload, `path#name` renames the asset"
+    )]
+    pub files: Vec<String>,
+    #[clap(flatten)]
+    pub args: Args,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct NextVersionArgs {
+    #[clap(
+        long,
+        default_value = "false",
+        help = "Print version, tag, bump level, base tag and commit count as JSON"
+    )]
+    pub json: bool,
+    #[clap(flatten)]
+    pub args: Args,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CompletionsArgs {
+    #[clap(value_enum, help = "Shell to register completions with")]
+    pub shell: Shell,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ManArgs {
+    #[clap(
+        long,
+        help = "Write easer.1 and a page per subcommand to this directory instead of printing easer.1"
+    )]
+    pub out_dir: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct VerifyArgs {
+    #[clap(
+        default_value = ".",
+        help = "Directory containing the downloaded assets"
+    )]
+    pub dir: String,
+    #[clap(
+        long,
+        value_enum,
+        help = "Only check signatures of this kind (default: any found)"
+    )]
+    pub method: Option<SignMethod>,
+    #[clap(
+        long,
+        env = "EASER_VERIFY_KEY",
+        help = "SSH allowed_signers file or minisign public key file"
+    )]
+    pub key: Option<String>,
+    #[clap(long, help = "Signer identity (principal) for SSH signatures")]
+    pub identity: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct LoginArgs {
+    #[clap(
+        long,
+        env = "EASER_PROFILE",
+        default_value = "default",
+        help = "Profile name to store the credentials under"
+    )]
+    pub profile: String,
+    #[clap(
+        long,
+        env = "EASER_API_URL",
+        help = "Gitee API base URL for this profile (default: https://gitee.com)"
+    )]
+    pub api_url: Option<String>,
+    #[clap(flatten)]
+    pub http: HttpArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct MirrorArgs {
+    #[clap(long, help = "Source repository, e.g. github:owner/repo")]
+    pub from: RepoRef,
+    #[clap(long, help = "Target repository, e.g. gitee:owner/repo")]
+    pub to: RepoRef,
+    #[clap(
+        long,
+        help = "API base URL of the source forge (default: its public API)"
+    )]
+    pub from_api_url: Option<String>,
+    #[clap(
+        long,
+        help = "API base URL of the target forge (default: its public API)"
+    )]
+    pub to_api_url: Option<String>,
+    #[clap(
+        long,
+        help = "Read the source token from this file instead of the forge's variable (e.g. GITHUB_TOKEN)"
+    )]
+    pub from_token_file: Option<String>,
+    #[clap(
+        long,
+        help = "Read the target token from this file instead of the forge's variable (e.g. GITEE_TOKEN)"
+    )]
+    pub to_token_file: Option<String>,
+    #[clap(
+        long,
+        help = "Read the source token from this environment variable instead of the forge's (e.g. UPSTREAM_TOKEN)"
+    )]
+    pub from_token_env: Option<String>,
+    #[clap(
+        long,
+        help = "Read the target token from this environment variable instead of the forge's (e.g. MIRROR_TOKEN)"
+    )]
+    pub to_token_env: Option<String>,
+    #[clap(
+        long,
+        default_value = "zh-cn",
+        help = "Language for messages (e.g., en-us, zh-cn)"
+    )]
+    pub lang: String,
+    #[clap(
+        long,
+        default_value = "false",
+        help = "List the releases and assets that would be copied without writing anything"
+    )]
+    pub dry_run: bool,
+    #[clap(flatten)]
+    pub http: HttpArgs,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_flat_invocation_is_release() {
+        let cli = Cli::try_parse_from(["easer", "--owner", "o", "--tag-name", "v1.0.0"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.release.owner.as_deref(), Some("o"));
+
+        let cli =
+            Cli::try_parse_from(["easer", "upload", "v1.0.0", "a.zip", "b.zip", "--repo", "r"])
+                .unwrap();
+        match cli.command {
+            Some(Command::Upload(upload)) => {
+                assert_eq!(upload.tag, "v1.0.0");
+                assert_eq!(upload.files, vec!["a.zip", "b.zip"]);
+                assert_eq!(upload.args.repo.as_deref(), Some("r"));
+            }
+            other => panic!("expected upload, got {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["easer", "--owner", "o", "release"]).is_err());
+    }
 }
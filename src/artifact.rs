@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+/// An `--artifacts` entry: `path` or `path#display-name`. An existing file
+/// whose name contains `#` is taken as is
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactSpec {
+    pub path: PathBuf,
+    /// Name the asset is uploaded under, `None` keeps the on-disk file name
+    pub display_name: Option<String>,
+}
+
+impl ArtifactSpec {
+    pub fn parse(spec: &str) -> Self {
+        if Path::new(spec).is_file() {
+            return ArtifactSpec {
+                path: PathBuf::from(spec),
+                display_name: None,
+            };
+        }
+        if let Some((path, name)) = spec.rsplit_once('#') {
+            // A '/' after the '#' means it was part of a directory name, not a display name
+            if !path.is_empty() && !name.is_empty() && !name.contains(['/', '\\']) {
+                return ArtifactSpec {
+                    path: PathBuf::from(path),
+                    display_name: Some(name.to_string()),
+                };
+            }
+        }
+        ArtifactSpec {
+            path: PathBuf::from(spec),
+            display_name: None,
+        }
+    }
+
+    /// Name to upload under: the display name if given, otherwise the file name
+    pub fn upload_name(&self) -> Option<String> {
+        self.display_name.clone().or_else(|| {
+            self.path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+    }
+}
+
+/// Expand `--artifacts` entries that are glob patterns into the files they
+/// match, in sorted order. Other entries, and patterns matching nothing, are
+/// kept as given so that the missing file is reported later
+pub fn expand_patterns(specs: &[String]) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for spec in specs {
+        let matches: Vec<String> = if spec.contains(['*', '?', '[']) {
+            glob::glob(spec)
+                .map(|paths| {
+                    paths
+                        .flatten()
+                        .filter(|path| path.is_file())
+                        .map(|path| path.to_string_lossy().to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        if matches.is_empty() {
+            expanded.push(spec.clone());
+        } else {
+            expanded.extend(matches);
+        }
+    }
+    expanded
+}
+
+/// An artifact read into memory, ready to upload
+pub struct LoadedArtifact {
+    pub filename: String,
+    pub content: Vec<u8>,
+    pub path: PathBuf,
+}
+
+/// Detect the content type from magic bytes, falling back to the file extension
+pub fn content_type(name: &str, content: &[u8]) -> String {
+    if let Some(kind) = infer::get(content) {
+        return kind.mime_type().to_string();
+    }
+    mime_guess::from_path(Path::new(name))
+        .first_raw()
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_name() {
+        let spec = ArtifactSpec::parse("target/release/easer#easer-linux-x86_64");
+        assert_eq!(spec.path, PathBuf::from("target/release/easer"));
+        assert_eq!(spec.upload_name().unwrap(), "easer-linux-x86_64");
+
+        let spec = ArtifactSpec::parse("dist/#1/app.zip");
+        assert_eq!(spec.path, PathBuf::from("dist/#1/app.zip"));
+        assert_eq!(spec.upload_name().unwrap(), "app.zip");
+    }
+
+    #[test]
+    fn test_parse_file_name_with_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app#1.zip");
+        std::fs::write(&file, "zip").unwrap();
+        let file = file.to_str().unwrap();
+
+        let spec = ArtifactSpec::parse(file);
+        assert_eq!(spec.path, PathBuf::from(file));
+        assert_eq!(spec.upload_name().unwrap(), "app#1.zip");
+
+        let spec = ArtifactSpec::parse(&format!("{}#app.zip", file));
+        assert_eq!(spec.path, PathBuf::from(file));
+        assert_eq!(spec.upload_name().unwrap(), "app.zip");
+    }
+
+    #[test]
+    fn test_expand_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.tar.gz", "a.tar.gz", "notes.txt"] {
+            std::fs::write(dir.path().join(name), name).unwrap();
+        }
+        let root = dir.path().to_str().unwrap();
+
+        let expanded = expand_patterns(&[
+            format!("{}/*.tar.gz", root),
+            format!("{}/notes.txt#NOTES", root),
+            format!("{}/*.zip", root),
+        ]);
+
+        assert_eq!(
+            expanded,
+            vec![
+                format!("{}/a.tar.gz", root),
+                format!("{}/b.tar.gz", root),
+                format!("{}/notes.txt#NOTES", root),
+                format!("{}/*.zip", root),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_type() {
+        assert_eq!(
+            content_type("a.gz", &[0x1f, 0x8b, 0x08, 0x00]),
+            "application/gzip"
+        );
+        assert_eq!(content_type("notes.txt", b"hello"), "text/plain");
+        assert_eq!(
+            content_type("easer-linux", b"\x00\x01"),
+            "application/octet-stream"
+        );
+        // Magic bytes win over a misleading extension
+        assert_eq!(
+            content_type("app.txt", b"PK\x03\x04rest"),
+            "application/zip"
+        );
+    }
+}
@@ -0,0 +1,673 @@
+use crate::artifact::LoadedArtifact;
+use crate::gitea::GiteaBackend;
+use crate::gitee::GiteeBackend;
+use crate::github::GithubBackend;
+use crate::gitlab::GitlabBackend;
+use crate::http::{self, HttpConfig};
+use crate::token::Token;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use reqwest::{header, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// Forge a release is published to
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    Gitee,
+    Github,
+    /// Gitea and Forgejo share one API
+    #[value(alias = "forgejo")]
+    #[serde(alias = "forgejo")]
+    Gitea,
+    Gitlab,
+}
+
+impl Forge {
+    pub fn default_api_url(&self) -> &'static str {
+        match self {
+            Forge::Gitee => crate::gitee::DEFAULT_API_URL,
+            Forge::Github => "https://api.github.com",
+            Forge::Gitea => crate::gitea::DEFAULT_API_URL,
+            Forge::Gitlab => crate::gitlab::DEFAULT_API_URL,
+        }
+    }
+
+    /// Environment variable holding the access token for this forge
+    pub fn token_env(&self) -> &'static str {
+        match self {
+            Forge::Gitee => "GITEE_TOKEN",
+            Forge::Github => "GITHUB_TOKEN",
+            Forge::Gitea => "GITEA_TOKEN",
+            Forge::Gitlab => "GITLAB_TOKEN",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Forge::Gitee => "Gitee",
+            Forge::Github => "GitHub",
+            Forge::Gitea => "Gitea",
+            Forge::Gitlab => "GitLab",
+        }
+    }
+}
+
+/// A repository on a specific forge, written `forge:owner/repo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoRef {
+    pub forge: Forge,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl FromStr for RepoRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected forge:owner/repo, got '{}'", s);
+        let (forge, path) = s.split_once(':').ok_or_else(invalid)?;
+        let forge = Forge::from_str(forge, true)?;
+        // GitLab owners may contain subgroups, so only the last segment is the repo
+        match path.rsplit_once('/') {
+            Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() => Ok(RepoRef {
+                forge,
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for RepoRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}/{}",
+            self.forge.display_name(),
+            self.owner,
+            self.repo
+        )
+    }
+}
+
+/// Release metadata sent when creating or updating a release
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Release {
+    pub tag_name: String,
+    pub target_commitish: String,
+    pub name: String,
+    pub body: String,
+    pub draft: bool,
+    pub prerelease: bool,
+}
+
+/// Forges send `null` for absent strings and flags; read those as the default
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// A release as returned by the forge
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RemoteRelease {
+    /// Numeric id; GitLab has none and identifies releases by tag
+    #[serde(default)]
+    pub id: Option<i64>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub tag_name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub target_commitish: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub body: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub draft: bool,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    /// Asset upload endpoint, when the forge hands one out (GitHub's is a URI template)
+    #[serde(default)]
+    pub upload_url: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub assets: Vec<RemoteAsset>,
+}
+
+impl RemoteRelease {
+    /// The id GitHub, Gitee and Gitea address the release by in their URLs
+    pub fn numeric_id(&self) -> Result<i64, Box<dyn Error + Send + Sync>> {
+        self.id
+            .ok_or_else(|| format!("Release {} has no id", self.tag_name).into())
+    }
+}
+
+/// A downloadable file attached to a release
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RemoteAsset {
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub browser_download_url: String,
+    /// API endpoint of the asset; GitHub serves the file of private and
+    /// draft releases only from there
+    #[serde(default, rename = "url")]
+    pub api_url: Option<String>,
+}
+
+/// Non-success HTTP response from a forge API
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.status, self.body)
+    }
+}
+
+impl Error for ApiError {}
+
+/// Percent-encode one path segment, so that a tag or branch like
+/// `release/1.0` or a GitLab project id like `group/project` stays one segment
+pub fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Normalize a user supplied API URL: accept it with or without the forge's
+/// API path, e.g. `/api/v5`, and with or without a trailing slash
+pub fn normalize_api_url(api_url: &str, api_path: &str) -> String {
+    let trimmed = api_url.trim_end_matches('/');
+    trimmed
+        .strip_suffix(api_path)
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+/// Request to a forge API carrying the token as `authorization`
+pub fn api_request(
+    config: &BackendConfig,
+    method: Method,
+    url: &str,
+    authorization: &str,
+    accept: &str,
+) -> reqwest::RequestBuilder {
+    config
+        .client
+        .request(method, url)
+        .header(header::AUTHORIZATION, authorization)
+        .header(header::ACCEPT, accept)
+}
+
+/// Send `request` and return the status and the body with the token scrubbed.
+/// Pre-flight checks report failures as text, hence the `String` error
+pub async fn fetch(
+    request: reqwest::RequestBuilder,
+    token: &Token,
+) -> Result<(StatusCode, String), String> {
+    let res = request.send().await.map_err(|e| e.to_string())?;
+    let status = res.status();
+    let body = res.text().await.map_err(|e| e.to_string())?;
+    Ok((status, token.redact(&body)))
+}
+
+/// [`fetch`] that fails on a non-success status
+pub async fn fetch_ok(request: reqwest::RequestBuilder, token: &Token) -> Result<String, String> {
+    match fetch(request, token).await? {
+        (status, body) if status.is_success() => Ok(body),
+        (status, body) => Err(format!("{} - {}", status, body)),
+    }
+}
+
+/// Log the status and URL of a response at debug level
+fn log_response(res: &reqwest::Response, token: &Token) {
+    debug!(
+        status = res.status().as_u16(),
+        url = token.redact(res.url().as_str()).as_str(),
+        "API response"
+    );
+}
+
+/// Turn a response into `T`, or an [`ApiError`] with the token scrubbed from the body
+pub async fn read_json<T: DeserializeOwned>(
+    res: reqwest::Response,
+    token: &Token,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    log_response(&res, token);
+    let status = res.status();
+    let body = token.redact(&res.text().await?);
+    trace!(body = body.as_str(), "API response body");
+    if !status.is_success() {
+        return Err(Box::new(ApiError { status, body }));
+    }
+    serde_json::from_str(&body).map_err(|e| format!("Failed to parse API response: {}", e).into())
+}
+
+/// Like [`read_json`] for endpoints whose response body is irrelevant
+pub async fn expect_success(
+    res: reqwest::Response,
+    token: &Token,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    log_response(&res, token);
+    let status = res.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = token.redact(&res.text().await.unwrap_or_default());
+    Err(Box::new(ApiError { status, body }))
+}
+
+/// Download a file with the backend's token, if it has one. reqwest drops
+/// the `Authorization` header when the download redirects to another host,
+/// as release assets usually do to a CDN
+pub async fn download_file(
+    config: &BackendConfig,
+    authorization: &str,
+    url: &str,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let mut request = config
+        .client
+        .get(url)
+        .header(header::ACCEPT, "application/octet-stream")
+        .timeout(config.upload_timeout);
+    if !config.token.expose().is_empty() {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    let res = request.send().await?;
+    log_response(&res, &config.token);
+    let status = res.status();
+    if !status.is_success() {
+        let body = config.token.redact(&res.text().await.unwrap_or_default());
+        return Err(Box::new(ApiError { status, body }));
+    }
+    Ok(res.bytes().await?.to_vec())
+}
+
+/// Check an upload response and read the asset it describes. Some forges and
+/// proxies answer uploads with an empty or partial body, so only the status
+/// decides success; an unreadable body gives `None`
+pub async fn read_upload<T: DeserializeOwned>(
+    res: reqwest::Response,
+    token: &Token,
+) -> Result<Option<T>, Box<dyn Error + Send + Sync>> {
+    log_response(&res, token);
+    let status = res.status();
+    let body = token.redact(&res.text().await.unwrap_or_default());
+    trace!(body = body.as_str(), "API response body");
+    if !status.is_success() {
+        return Err(Box::new(ApiError { status, body }));
+    }
+    Ok(serde_json::from_str(&body).ok())
+}
+
+/// [`read_upload`] for forges that answer with a release asset
+pub async fn read_uploaded_asset(
+    res: reqwest::Response,
+    token: &Token,
+    filename: &str,
+) -> Result<RemoteAsset, Box<dyn Error + Send + Sync>> {
+    let asset: Option<RemoteAsset> = read_upload(res, token).await?;
+    let mut asset = asset.unwrap_or_default();
+    if asset.name.is_empty() {
+        asset.name = filename.to_string();
+    }
+    Ok(asset)
+}
+
+/// Collect a paginated listing; `request` builds the request for a 1-based page
+pub async fn fetch_all_pages<T, F>(
+    mut request: F,
+    per_page: usize,
+    token: &Token,
+) -> Result<Vec<T>, Box<dyn Error + Send + Sync>>
+where
+    T: DeserializeOwned,
+    F: FnMut(usize) -> reqwest::RequestBuilder,
+{
+    let mut items = Vec::new();
+    for page in 1.. {
+        let batch: Vec<T> = read_json(request(page).send().await?, token).await?;
+        let last = batch.len() < per_page;
+        items.extend(batch);
+        if last {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+/// Pre-flight checks every backend reports on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckKind {
+    Token,
+    Repository,
+    PushPermission,
+    Target,
+    TagAbsent,
+    ReleaseAbsent,
+}
+
+pub struct CheckOutcome {
+    pub kind: CheckKind,
+    /// `Ok(detail)` or `Err(reason)`; `detail` may be empty
+    pub outcome: Result<String, String>,
+}
+
+/// Everything a backend needs to talk to one repository
+pub struct BackendConfig {
+    pub api_url: String,
+    pub owner: String,
+    pub repo: String,
+    pub token: Token,
+    pub client: reqwest::Client,
+    pub upload_timeout: Duration,
+}
+
+/// Release operations of a forge's REST API
+#[async_trait]
+pub trait ReleaseBackend: Send + Sync {
+    fn forge(&self) -> Forge;
+
+    /// Web URL of the repository, e.g. `https://gitee.com/owner/repo`
+    fn repo_web_url(&self) -> String;
+
+    /// Prefix that a commit SHA is appended to for links in release notes
+    fn commit_url_prefix(&self) -> String {
+        format!("{}/commit", self.repo_web_url())
+    }
+
+    /// Endpoint releases are created at, for logs and dry runs
+    fn releases_url(&self) -> String;
+
+    /// Endpoint assets of release `release_id` are uploaded to
+    fn assets_url(&self, release_id: &str) -> String;
+
+    /// `Authorization` header value for `token`
+    fn authorization(&self, token: &str) -> String;
+
+    async fn create_release(
+        &self,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>>;
+
+    /// Every release of the repository, newest first
+    async fn list_releases(&self) -> Result<Vec<RemoteRelease>, Box<dyn Error + Send + Sync>>;
+
+    async fn find_release_by_tag(
+        &self,
+        tag: &str,
+    ) -> Result<Option<RemoteRelease>, Box<dyn Error + Send + Sync>>;
+
+    async fn update_release(
+        &self,
+        existing: &RemoteRelease,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>>;
+
+    /// Upload one artifact. The download URL of the returned asset is empty
+    /// when the forge does not report one
+    async fn upload_asset(
+        &self,
+        release: &RemoteRelease,
+        artifact: &LoadedArtifact,
+    ) -> Result<RemoteAsset, Box<dyn Error + Send + Sync>>;
+
+    /// Content of an asset of one of the repository's releases
+    async fn download_asset(
+        &self,
+        asset: &RemoteAsset,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+
+    async fn delete_release(
+        &self,
+        release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Delete what uploads to `release` left outside of it. Attachments of
+    /// the release itself go with [`ReleaseBackend::delete_release`]
+    async fn delete_uploads(
+        &self,
+        _release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn tag_exists(&self, tag: &str) -> Result<bool, Box<dyn Error + Send + Sync>>;
+
+    async fn delete_tag(&self, tag: &str) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Validate token, repository, target and tag without writing anything
+    async fn preflight_checks(&self, tag_name: &str, target_commitish: &str) -> Vec<CheckOutcome>;
+}
+
+/// The current user; GitLab names the field `username`
+#[derive(Deserialize)]
+struct UserResponse {
+    #[serde(alias = "username")]
+    login: String,
+}
+
+/// A repository as GitHub and Gitea (`permissions`) or Gitee (`permission`)
+/// describe it
+#[derive(Deserialize)]
+struct RepoResponse {
+    #[serde(default, alias = "permission")]
+    permissions: Option<RepoPermissions>,
+}
+
+#[derive(Deserialize)]
+struct RepoPermissions {
+    push: bool,
+}
+
+/// Push permission check from a GitHub, Gitee or Gitea repository response
+pub fn push_permission(repo_body: &str) -> Result<String, String> {
+    let repo: RepoResponse = serde_json::from_str(repo_body).map_err(|e| e.to_string())?;
+    match repo.permissions {
+        Some(permissions) if !permissions.push => Err("no push permission".to_string()),
+        Some(_) => Ok(String::new()),
+        None => Ok("not reported".to_string()),
+    }
+}
+
+/// Endpoints and field mapping a backend hands to [`run_preflight_checks`]
+pub struct PreflightEndpoints {
+    /// The current user, whose `login` identifies the token. `None` where
+    /// tokens may not read it, and only a 401 from the repository fails the
+    /// token check
+    pub user_url: Option<String>,
+    pub repo_url: String,
+    /// `owner/repo`, shown when the repository check passes
+    pub repo_name: String,
+    /// Endpoint that resolves the target commitish
+    pub commit_url: String,
+    /// Push permission check from the repository response body
+    pub push: fn(&str) -> Result<String, String>,
+}
+
+/// The pre-flight checks of every backend, read from its `endpoints` with
+/// requests built by `request`
+pub async fn run_preflight_checks(
+    backend: &dyn ReleaseBackend,
+    request: &(dyn Fn(&str) -> reqwest::RequestBuilder + Sync),
+    token: &Token,
+    endpoints: PreflightEndpoints,
+    tag_name: &str,
+    target_commitish: &str,
+) -> Vec<CheckOutcome> {
+    let user = match &endpoints.user_url {
+        Some(url) => Some(fetch_ok(request(url), token).await.and_then(|body| {
+            serde_json::from_str::<UserResponse>(&body)
+                .map(|user| user.login)
+                .map_err(|e| e.to_string())
+        })),
+        None => None,
+    };
+    let repo = fetch(request(&endpoints.repo_url), token).await;
+    let token_check = match (user, &repo) {
+        (Some(user), _) => user,
+        (None, Ok((StatusCode::UNAUTHORIZED, body))) => {
+            Err(format!("{} - {}", StatusCode::UNAUTHORIZED, body))
+        }
+        (None, Ok(_)) => Ok(String::new()),
+        (None, Err(e)) => Err(e.clone()),
+    };
+    let repo = repo.and_then(|(status, body)| {
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(format!("{} - {}", status, body))
+        }
+    });
+    let push = match &repo {
+        Ok(body) => (endpoints.push)(body),
+        Err(e) => Err(e.clone()),
+    };
+    let target = fetch_ok(request(&endpoints.commit_url), token)
+        .await
+        .map(|_| target_commitish.to_string());
+
+    vec![
+        CheckOutcome {
+            kind: CheckKind::Token,
+            outcome: token_check,
+        },
+        CheckOutcome {
+            kind: CheckKind::Repository,
+            outcome: repo.map(|_| endpoints.repo_name),
+        },
+        CheckOutcome {
+            kind: CheckKind::PushPermission,
+            outcome: push,
+        },
+        CheckOutcome {
+            kind: CheckKind::Target,
+            outcome: target,
+        },
+        CheckOutcome {
+            kind: CheckKind::TagAbsent,
+            outcome: tag_absent(backend, tag_name).await,
+        },
+        CheckOutcome {
+            kind: CheckKind::ReleaseAbsent,
+            outcome: release_absent(backend, tag_name).await,
+        },
+    ]
+}
+
+/// Pre-flight check shared by the backends: the tag must not exist yet
+pub async fn tag_absent(backend: &dyn ReleaseBackend, tag_name: &str) -> Result<String, String> {
+    match backend.tag_exists(tag_name).await {
+        Ok(false) => Ok(tag_name.to_string()),
+        Ok(true) => Err(format!("{} already exists", tag_name)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Pre-flight check shared by the backends: the tag must not carry a release yet
+pub async fn release_absent(
+    backend: &dyn ReleaseBackend,
+    tag_name: &str,
+) -> Result<String, String> {
+    match backend.find_release_by_tag(tag_name).await {
+        Ok(None) => Ok(tag_name.to_string()),
+        Ok(Some(release)) => Err(match release.id {
+            Some(id) => format!("release {} already exists for {}", id, tag_name),
+            None => format!("a release already exists for {}", tag_name),
+        }),
+        Err(e) => Err(match e.downcast_ref::<ApiError>() {
+            Some(api_error) => api_error.to_string(),
+            None => e.to_string(),
+        }),
+    }
+}
+
+/// Plain settings for a client of one repository, see [`connect`]
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub forge: Forge,
+    /// Default: the forge's public instance
+    pub api_url: Option<String>,
+    pub owner: String,
+    pub repo: String,
+    pub token: Token,
+    pub http: HttpConfig,
+}
+
+/// Build the release client for `config.forge`. Each backend strips its own
+/// API path suffix (`/api/v5`, `/api/v1`, ...) from the API URL
+pub fn connect(
+    config: &ClientConfig,
+) -> Result<Box<dyn ReleaseBackend>, Box<dyn Error + Send + Sync>> {
+    Ok(build_backend(
+        config.forge,
+        BackendConfig {
+            api_url: config
+                .api_url
+                .clone()
+                .unwrap_or_else(|| config.forge.default_api_url().to_string()),
+            owner: config.owner.clone(),
+            repo: config.repo.clone(),
+            token: config.token.clone(),
+            client: http::build_client(&config.http)?,
+            upload_timeout: config.http.upload_timeout,
+        },
+    ))
+}
+
+pub fn build_backend(forge: Forge, config: BackendConfig) -> Box<dyn ReleaseBackend> {
+    match forge {
+        Forge::Gitee => Box::new(GiteeBackend::new(config)),
+        Forge::Github => Box::new(GithubBackend::new(config)),
+        Forge::Gitea => Box::new(GiteaBackend::new(config)),
+        Forge::Gitlab => Box::new(GitlabBackend::new(config)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_api_url() {
+        assert_eq!(
+            normalize_api_url("https://gitee.example.com/api/v5/", "/api/v5"),
+            "https://gitee.example.com"
+        );
+        assert_eq!(
+            normalize_api_url("https://codeberg.org/", "/api/v1"),
+            "https://codeberg.org"
+        );
+    }
+
+    #[test]
+    fn test_push_permission() {
+        assert!(push_permission(r#"{"permissions": {"push": true}}"#).is_ok());
+        assert_eq!(
+            push_permission(r#"{"permission": {"push": false}}"#),
+            Err("no push permission".to_string())
+        );
+        assert_eq!(push_permission("{}"), Ok("not reported".to_string()));
+    }
+}
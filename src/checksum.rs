@@ -0,0 +1,77 @@
+use sha2::{Digest, Sha256, Sha512};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// File name of the checksum manifest uploaded alongside the artifacts
+pub const MANIFEST_NAME: &str = "SHA256SUMS";
+
+pub struct ArtifactDigest {
+    pub filename: String,
+    pub sha256: String,
+    pub sha512: String,
+}
+
+impl ArtifactDigest {
+    pub fn compute(filename: &str, content: &[u8]) -> Self {
+        ArtifactDigest {
+            filename: filename.to_string(),
+            sha256: format!("{:x}", Sha256::digest(content)),
+            sha512: format!("{:x}", Sha512::digest(content)),
+        }
+    }
+}
+
+/// Render digests in the coreutils `sha256sum` format (`<hash>  <name>`)
+pub fn sha256sums(digests: &[ArtifactDigest]) -> String {
+    digests
+        .iter()
+        .map(|d| format!("{}  {}\n", d.sha256, d.filename))
+        .collect()
+}
+
+/// Render digests as a Markdown table for the release body
+pub fn markdown_table(digests: &[ArtifactDigest]) -> String {
+    let mut table = String::from("## 🔐 Checksums\n");
+    table.push_str("| File | SHA-256 | SHA-512 |\n");
+    table.push_str("| --- | --- | --- |\n");
+    for d in digests {
+        table.push_str(&format!(
+            "| `{}` | `{}` | `{}` |\n",
+            d.filename, d.sha256, d.sha512
+        ));
+    }
+    table
+}
+
+/// Write the SHA256SUMS manifest into `dir` and return its path
+pub async fn write_manifest(dir: &Path, digests: &[ArtifactDigest]) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(dir).await?;
+    let path = dir.join(MANIFEST_NAME);
+    fs::write(&path, sha256sums(digests)).await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256sums_format() {
+        let digests = vec![ArtifactDigest::compute("hello.txt", b"hello\n")];
+        assert_eq!(
+            sha256sums(&digests),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  hello.txt\n"
+        );
+        assert_eq!(digests[0].sha512.len(), 128);
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let digests = vec![ArtifactDigest::compute("a.zip", b"")];
+        let table = markdown_table(&digests);
+        assert!(table.contains("| File | SHA-256 | SHA-512 |"));
+        assert!(table.contains(
+            "| `a.zip` | `e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855` |"
+        ));
+    }
+}
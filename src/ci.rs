@@ -0,0 +1,272 @@
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Dotenv report written under GitLab CI, relative to the job's working directory
+pub const DEFAULT_DOTENV: &str = "easer.env";
+
+/// Markdown summary written under GitLab CI and Gitee Go, which have no job
+/// summary of their own, next to the dotenv report
+pub const SUMMARY_FILE: &str = "easer-summary.md";
+
+/// CI system easer is running in, detected from its predefined variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiSystem {
+    GithubActions,
+    GiteeGo,
+    GitlabCi,
+}
+
+impl CiSystem {
+    pub fn detect() -> Option<Self> {
+        Self::detect_from(|name| std::env::var(name).ok())
+    }
+
+    fn detect_from(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        if var("GITHUB_ACTIONS").as_deref() == Some("true") {
+            Some(CiSystem::GithubActions)
+        } else if var("GITLAB_CI").as_deref() == Some("true") {
+            Some(CiSystem::GitlabCi)
+        } else if var("GITEE_PIPELINE_NAME").is_some() {
+            Some(CiSystem::GiteeGo)
+        } else {
+            None
+        }
+    }
+}
+
+/// Values later CI steps need from a published release
+#[derive(Debug, Clone)]
+pub struct StepOutputs {
+    pub tag: String,
+    /// The tag without `--tag-prefix`
+    pub version: String,
+    pub name: String,
+    pub release_id: Option<i64>,
+    pub release_url: Option<String>,
+    /// Release notes, only used in the job summary
+    pub body: String,
+}
+
+impl StepOutputs {
+    fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("tag", self.tag.clone()),
+            ("version", self.version.clone()),
+            ("name", self.name.clone()),
+            (
+                "release_id",
+                self.release_id.map(|id| id.to_string()).unwrap_or_default(),
+            ),
+            ("release_url", self.release_url.clone().unwrap_or_default()),
+        ]
+    }
+
+    /// Markdown job summary with a link to the release and its notes
+    fn summary(&self) -> String {
+        let title = match &self.release_url {
+            Some(url) => format!("## 🚀 [{}]({})", self.name, url),
+            None => format!("## 🚀 {}", self.name),
+        };
+        format!("{}\n\nTag: `{}`\n\n{}\n", title, self.tag, self.body)
+    }
+}
+
+fn append(path: &Path, content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// A heredoc delimiter that does not occur in `value`. Release notes are
+/// written by whoever pushes commits, so a fixed delimiter would let a commit
+/// message end the value early and set other outputs
+fn heredoc_delimiter(value: &str) -> String {
+    loop {
+        let random = RandomState::new().build_hasher().finish();
+        let delimiter = format!("EASER_EOF_{:016x}", random);
+        if !value.contains(&delimiter) {
+            return delimiter;
+        }
+    }
+}
+
+/// `key=value` lines, with GitHub's heredoc syntax for values spanning lines
+fn github_outputs(outputs: &StepOutputs) -> String {
+    outputs
+        .pairs()
+        .into_iter()
+        .map(|(key, value)| {
+            if value.contains('\n') {
+                let delimiter = heredoc_delimiter(&value);
+                format!("{}<<{}\n{}\n{}\n", key, delimiter, value, delimiter)
+            } else {
+                format!("{}={}\n", key, value)
+            }
+        })
+        .collect()
+}
+
+/// `EASER_KEY=value` lines. Dotenv reports are single-line only
+fn dotenv(outputs: &StepOutputs) -> String {
+    outputs
+        .pairs()
+        .into_iter()
+        .map(|(key, value)| {
+            format!(
+                "EASER_{}={}\n",
+                key.to_uppercase(),
+                value.replace(['\r', '\n'], " ")
+            )
+        })
+        .collect()
+}
+
+/// Gitee Go reads output parameters from `##[set-output key=value]` lines
+/// in the step log
+fn gitee_go_outputs(outputs: &StepOutputs) -> String {
+    outputs
+        .pairs()
+        .into_iter()
+        .map(|(key, value)| {
+            format!(
+                "##[set-output {}={}]\n",
+                key,
+                value.replace(['\r', '\n'], " ")
+            )
+        })
+        .collect()
+}
+
+fn summary_path(dotenv_path: &Path) -> PathBuf {
+    dotenv_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(SUMMARY_FILE)
+}
+
+/// Hand the outputs to the CI system in its native format:
+/// `$GITHUB_OUTPUT` plus a Markdown summary in `$GITHUB_STEP_SUMMARY`, Gitee
+/// Go output parameters on stderr, or a GitLab dotenv report at `dotenv_path`.
+/// GitLab and Gitee Go get the summary in [`SUMMARY_FILE`]
+pub fn write_outputs(
+    ci: CiSystem,
+    outputs: &StepOutputs,
+    dotenv_path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match ci {
+        CiSystem::GithubActions => {
+            if let Some(path) = std::env::var_os("GITHUB_OUTPUT") {
+                append(Path::new(&path), &github_outputs(outputs))?;
+            }
+            if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") {
+                append(Path::new(&path), &outputs.summary())?;
+            }
+        }
+        // stdout is reserved for results, and the step log has both streams
+        CiSystem::GiteeGo => {
+            eprint!("{}", gitee_go_outputs(outputs));
+            append(&summary_path(dotenv_path), &outputs.summary())?;
+        }
+        CiSystem::GitlabCi => {
+            append(dotenv_path, &dotenv(outputs))?;
+            append(&summary_path(dotenv_path), &outputs.summary())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn outputs() -> StepOutputs {
+        StepOutputs {
+            tag: "v1.2.0".to_string(),
+            version: "1.2.0".to_string(),
+            name: "v1.2.0".to_string(),
+            release_id: Some(42),
+            release_url: Some("https://gitee.com/acme/tool/releases/tag/v1.2.0".to_string()),
+            body: "## ✨ Features\n\n- add upload".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_detect_ci_system() {
+        let detect = |vars: &[(&str, &str)]| {
+            CiSystem::detect_from(|name| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(
+            detect(&[("GITHUB_ACTIONS", "true")]),
+            Some(CiSystem::GithubActions)
+        );
+        assert_eq!(detect(&[("GITLAB_CI", "true")]), Some(CiSystem::GitlabCi));
+        assert_eq!(
+            detect(&[("GITEE_PIPELINE_NAME", "release")]),
+            Some(CiSystem::GiteeGo)
+        );
+        assert_eq!(detect(&[("CI", "true")]), None);
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let outputs = StepOutputs {
+            name: "Release\n1.2.0".to_string(),
+            ..outputs()
+        };
+
+        let github = github_outputs(&outputs);
+        assert!(github.starts_with("tag=v1.2.0\nversion=1.2.0\n"));
+        let heredoc = github.split("name<<").nth(1).unwrap();
+        let (delimiter, rest) = heredoc.split_once('\n').unwrap();
+        assert!(delimiter.starts_with("EASER_EOF_"));
+        assert!(rest.starts_with(&format!("Release\n1.2.0\n{}\n", delimiter)));
+        assert!(github.contains("release_id=42\n"));
+
+        let dotenv = dotenv(&outputs);
+        assert!(dotenv.contains("EASER_VERSION=1.2.0\n"));
+        assert!(dotenv.contains("EASER_NAME=Release 1.2.0\n"));
+        assert!(
+            dotenv.contains("EASER_RELEASE_URL=https://gitee.com/acme/tool/releases/tag/v1.2.0\n")
+        );
+
+        assert!(gitee_go_outputs(&outputs).contains("##[set-output tag=v1.2.0]\n"));
+    }
+
+    #[test]
+    fn test_write_gitlab_dotenv_appends() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_DOTENV);
+        std::fs::write(&path, "OTHER=1\n").unwrap();
+
+        write_outputs(CiSystem::GitlabCi, &outputs(), &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("OTHER=1\nEASER_TAG=v1.2.0\n"));
+        let summary = std::fs::read_to_string(dir.path().join(SUMMARY_FILE)).unwrap();
+        assert!(summary.contains("- add upload"));
+    }
+
+    #[test]
+    fn test_heredoc_delimiter_not_in_value() {
+        let first = heredoc_delimiter("notes");
+        let second = heredoc_delimiter("notes");
+        assert_ne!(first, second);
+
+        let value = format!("{}\nrelease_url=https://example.com", first);
+        let delimiter = heredoc_delimiter(&value);
+        assert!(!value.contains(&delimiter));
+    }
+}
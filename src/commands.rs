@@ -0,0 +1,1063 @@
+use crate::args::Args;
+use crate::config::{self, Config};
+use crate::review;
+use easer::artifact;
+use easer::backend::{self, ClientConfig};
+use easer::ci::{self, CiSystem, StepOutputs};
+use easer::conventional::{Bump, ChangelogGenerator, ReleaseInfo};
+use easer::error::{EaserError, ErrorKind};
+use easer::http::HttpConfig;
+use easer::messages::Messages;
+use easer::output::{self, OutputFormat, ReleaseReport};
+use easer::release::{ReleaseOutcome, ReleaseRequest, ReleaseTarget};
+use easer::remote;
+use easer::token::Token;
+use std::error::Error;
+use std::path::Path;
+use tracing::{error, info, warn};
+
+/// The repository given by `--owner`/`--repo` on the command line, otherwise
+/// the targets listed in the config file, otherwise the layered owner and repo
+/// or the repository the `origin` remote points at. Only the half of the
+/// owner/repo that is missing is inferred
+fn release_targets(
+    args: &Args,
+    api_base_url: Option<&str>,
+) -> Result<Vec<ReleaseTarget>, Box<dyn Error + Send + Sync>> {
+    if !args.repo_from_command_line {
+        let path = config::config_path(args.config.as_deref(), &args.repo_path);
+        let targets = Config::load(&path)?.targets;
+        if !targets.is_empty() {
+            return Ok(targets
+                .into_iter()
+                .map(|target| ReleaseTarget {
+                    token_env: target
+                        .token_env
+                        .unwrap_or_else(|| target.forge.token_env().to_string()),
+                    forge: target.forge,
+                    owner: target.owner,
+                    repo: target.repo,
+                    api_url: target.api_url,
+                    token: None,
+                    token_file: target.token_file,
+                })
+                .collect());
+        }
+    }
+    let (owner, repo) = match (&args.owner, &args.repo) {
+        (Some(owner), Some(repo)) => (owner.clone(), repo.clone()),
+        (owner, repo) => {
+            let inferred = remote::infer_repo(&args.repo_path)?;
+            let owner = owner.clone().unwrap_or(inferred.owner);
+            let repo = repo.clone().unwrap_or(inferred.repo);
+            info!(
+                "Using repository {}/{} from remote '{}'",
+                owner,
+                repo,
+                remote::DEFAULT_REMOTE
+            );
+            (owner, repo)
+        }
+    };
+    Ok(vec![ReleaseTarget {
+        api_url: api_base_url.map(str::to_string),
+        token: args.token.clone(),
+        token_file: args.token_file.clone(),
+        ..ReleaseTarget::new(args.forge, owner, repo)
+    }])
+}
+
+/// The library request for the release options
+fn release_request(
+    args: &Args,
+    api_base_url: Option<&str>,
+) -> Result<ReleaseRequest, Box<dyn Error + Send + Sync>> {
+    let mut request = ReleaseRequest::new()
+        .targets(release_targets(args, api_base_url)?)
+        .repo_path(&args.repo_path)
+        .tag_prefix(&args.tag_prefix)
+        .auto_gen_notes(args.auto_gen_notes)
+        .sections(args.sections.clone())
+        .draft(args.draft)
+        .prerelease(args.prerelease)
+        .artifacts(args.artifacts.clone().unwrap_or_default())
+        .checksums(args.checksums)
+        .preflight(!args.no_preflight)
+        .all_or_nothing(args.all_or_nothing)
+        .lang(&args.lang)
+        .http(HttpConfig::from(&args.http));
+    if let Some(tag_name) = &args.tag_name {
+        request = request.tag_name(tag_name);
+    }
+    if let Some(name) = &args.name {
+        request = request.name(name);
+    }
+    if let Some(body) = &args.body {
+        request = request.body(body);
+    }
+    if let Some(target) = &args.target_commitish {
+        request = request.target_commitish(target);
+    }
+    if let Some(tag) = &args.previous_tag {
+        request = request.previous_tag(tag);
+    }
+    if let Some(method) = args.sign {
+        request = request.sign(method, args.sign_key.clone());
+    }
+    Ok(request)
+}
+
+/// The changelog generator for the release options, without commit links
+fn changelog(args: &Args) -> Result<ChangelogGenerator, Box<dyn Error + Send + Sync>> {
+    let target = match &args.target_commitish {
+        Some(target) => target.clone(),
+        None => remote::current_target(&args.repo_path)?,
+    };
+    let mut changelog = ChangelogGenerator::new(&args.repo_path)
+        .target(target)
+        .tag_prefix(&args.tag_prefix)
+        .sections(args.sections.clone());
+    if let Some(tag) = &args.previous_tag {
+        changelog = changelog.previous_tag(tag);
+    }
+    if let Some(version) = &args.tag_name {
+        changelog = changelog.version(version);
+    }
+    Ok(changelog)
+}
+
+/// `--interactive`: the generated notes as reviewed on the terminal, with
+/// commit links to the first target
+fn review_notes(
+    args: &Args,
+    api_base_url: Option<&str>,
+    messages: &Messages<'_>,
+) -> Result<ReleaseInfo, Box<dyn Error + Send + Sync>> {
+    if !args.auto_gen_notes {
+        return Err(
+            EaserError::Config("--interactive requires --auto-gen-notes".to_string()).into(),
+        );
+    }
+    let mut changelog = changelog(args)?;
+    if let Some(prefix) = offline_commit_url_prefix(args, api_base_url) {
+        changelog = changelog.commit_url_prefix(prefix);
+    }
+    review::review_notes(&changelog, &args.repo_path, &args.tag_prefix, messages)
+}
+
+/// `easer check`: run the pre-flight phase on its own
+pub async fn check_release(
+    args: Args,
+    api_base_url: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    Ok(release_request(&args, api_base_url)?.check().await?)
+}
+
+/// Write the tag, version and URL of the first published release to the CI
+/// system's step outputs. A failure only warns, since the release exists by now
+fn write_ci_outputs(args: &Args, outcome: &ReleaseOutcome) {
+    if args.no_ci_outputs {
+        return;
+    }
+    let Some(ci) = CiSystem::detect() else {
+        return;
+    };
+    let Some(published) = outcome.targets.iter().find(|report| report.error.is_none()) else {
+        return;
+    };
+    let tag = &outcome.release.tag_name;
+    let outputs = StepOutputs {
+        tag: tag.clone(),
+        version: tag
+            .strip_prefix(args.tag_prefix.as_str())
+            .unwrap_or(tag)
+            .to_string(),
+        name: outcome.release.name.clone(),
+        release_id: published.id,
+        release_url: published.html_url.clone(),
+        body: outcome.release.body.clone(),
+    };
+    if let Err(e) = ci::write_outputs(ci, &outputs, Path::new(&args.dotenv)) {
+        warn!("Cannot write CI step outputs: {}", e);
+    }
+}
+
+/// One line per target: published, published with failed uploads, failed or skipped
+fn print_summary(outcome: &ReleaseOutcome, all_or_nothing: bool, messages: &Messages<'_>) {
+    for report in &outcome.targets {
+        let url = report.html_url.as_deref().unwrap_or_default();
+        match &report.error {
+            Some(e) => println!("[✗] {}: {}", report.target, e),
+            None if report.failed_uploads() > 0 => println!(
+                "[{}] {}: {} ({} {})",
+                if all_or_nothing { "✗" } else { "✓" },
+                report.target,
+                url,
+                report.failed_uploads(),
+                messages.uploads_failed
+            ),
+            None => println!("[✓] {}: {}", report.target, url),
+        }
+    }
+    for label in &outcome.skipped {
+        println!("[-] {}: {}", label, messages.target_skipped);
+    }
+}
+
+/// With `--output json`, a failure before any release was attempted still
+/// leaves one document on stdout: the error, its kind and the exit code
+pub fn early_failure(
+    output: OutputFormat,
+    e: impl Into<EaserError>,
+) -> Box<dyn Error + Send + Sync> {
+    let e = e.into();
+    if output == OutputFormat::Json {
+        if let Err(print_error) = output::print_error_json(&e) {
+            warn!("Cannot print the JSON error: {}", print_error);
+        }
+    }
+    e.into()
+}
+
+pub async fn create_release(
+    args: Args,
+    api_base_url: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let messages = Messages::new(&args.lang);
+    let mut request =
+        release_request(&args, api_base_url).map_err(|e| early_failure(args.output, e))?;
+
+    let reviewed = if args.interactive {
+        let notes = review_notes(&args, api_base_url, &messages)
+            .map_err(|e| early_failure(args.output, e))?;
+        request = request
+            .auto_gen_notes(false)
+            .tag_name(&notes.tag_name)
+            .name(&notes.name)
+            .body(&notes.body);
+        Some(notes)
+    } else {
+        None
+    };
+
+    if args.dry_run {
+        let plan = request
+            .dry_run()
+            .await
+            .map_err(|e| early_failure(args.output, e))?;
+        print!("{}", plan);
+        return Ok(());
+    }
+
+    let outcome = request
+        .publish()
+        .await
+        .map_err(|e| early_failure(args.output, e))?;
+    // Releases rolled back must not be handed to later steps
+    if !outcome.rolled_back {
+        write_ci_outputs(&args, &outcome);
+    }
+    // Notes saved during review are only needed to retry a failed release
+    if let (Some(notes), Ok(dir)) = (&reviewed, review::notes_dir(&args.repo_path)) {
+        if outcome.failed == 0 {
+            let _ = std::fs::remove_file(review::notes_path(&dir, &notes.tag_name));
+        }
+    }
+    let total = outcome.targets.len() + outcome.skipped.len();
+    match args.output {
+        OutputFormat::Json => output::print_json(&outcome.targets)?,
+        OutputFormat::Text if total > 1 => print_summary(&outcome, args.all_or_nothing, &messages),
+        OutputFormat::Text => {}
+    }
+
+    if outcome.failed == 0 {
+        return partial_upload(&outcome.targets, &messages);
+    }
+    if let [report] = outcome.targets.as_slice() {
+        if total == 1 {
+            let kind = report.error_kind.unwrap_or(ErrorKind::Other);
+            return Err(EaserError::new(kind, report.error.clone().unwrap_or_default()).into());
+        }
+    }
+    let err_msg = format!("{}: {}/{}", messages.targets_failed, outcome.failed, total);
+    error!("{}", err_msg);
+    Err(EaserError::new(failed_kind(&outcome.targets), err_msg).into())
+}
+
+/// The kind the failed targets share, e.g. `tag_conflict` when the release
+/// already exists everywhere it failed. With all-or-nothing a target whose
+/// uploads failed counts as a partial upload
+fn failed_kind(reports: &[ReleaseReport]) -> ErrorKind {
+    ErrorKind::common(reports.iter().filter_map(|report| {
+        report
+            .error_kind
+            .or((report.failed_uploads() > 0).then_some(ErrorKind::PartialUpload))
+    }))
+}
+
+/// Releases that exist but miss some of their artifacts
+fn partial_upload(
+    reports: &[ReleaseReport],
+    messages: &Messages<'_>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let failed: usize = reports.iter().map(ReleaseReport::failed_uploads).sum();
+    if failed == 0 {
+        return Ok(());
+    }
+    let total: usize = reports.iter().map(|report| report.assets.len()).sum();
+    let err_msg = format!("{}/{} {}", failed, total, messages.uploads_failed);
+    error!("{}", err_msg);
+    Err(EaserError::PartialUpload(err_msg).into())
+}
+
+/// `easer upload`: add files to a release that already exists on every target
+pub async fn upload_release_assets(
+    args: Args,
+    tag: &str,
+    files: &[String],
+    api_base_url: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let messages = Messages::new(&args.lang);
+    let reports = release_request(&args, api_base_url)
+        .map_err(|e| early_failure(args.output, e))?
+        .artifacts(artifact::expand_patterns(files))
+        .upload(tag)
+        .await
+        .map_err(|e| early_failure(args.output, e))?;
+
+    if args.output == OutputFormat::Json {
+        output::print_json(&reports)?;
+    }
+    let failed = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    if failed > 0 {
+        let err_msg = format!("{}: {}/{}", messages.targets_failed, failed, reports.len());
+        error!("{}", err_msg);
+        return Err(EaserError::new(failed_kind(&reports), err_msg).into());
+    }
+    partial_upload(&reports, &messages)
+}
+
+/// Commit links for notes printed without publishing: the first target's
+/// forge if one can be worked out, otherwise the origin remote
+fn offline_commit_url_prefix(args: &Args, api_base_url: Option<&str>) -> Option<String> {
+    let target = release_targets(args, api_base_url)
+        .ok()?
+        .into_iter()
+        .next()?;
+    let client = backend::connect(&ClientConfig {
+        forge: target.forge,
+        api_url: target.api_url,
+        owner: target.owner,
+        repo: target.repo,
+        token: Token::new(""),
+        http: HttpConfig::from(&args.http),
+    })
+    .ok()?;
+    Some(client.commit_url_prefix())
+}
+
+/// `easer changelog`: print the release notes the next release would get
+pub fn print_changelog(
+    args: &Args,
+    api_base_url: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut changelog = changelog(args)?;
+    if let Some(prefix) = offline_commit_url_prefix(args, api_base_url) {
+        changelog = changelog.commit_url_prefix(prefix);
+    }
+    let info = changelog.generate()?;
+    println!("# {}\n\n{}", info.name, info.body.trim_end());
+    Ok(())
+}
+
+/// `easer next-version`: print the version the next release would get, from
+/// local tags and commits only. Returns `false` when nothing is releasable
+pub fn print_next_version(args: &Args, json: bool) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let next = changelog(args)?.next_version()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&next)?);
+    } else {
+        println!("{}", next.version);
+    }
+    Ok(args.tag_name.is_some() || next.bump != Bump::None)
+}
+
+/// `easer lint`: list commits since the last release that do not follow
+/// Conventional Commits, failing if there are any
+pub fn lint_commits(args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let messages = Messages::new(&args.lang);
+    let (total, invalid) = changelog(args)?.lint()?;
+    for commit in &invalid {
+        println!(
+            "[✗] {} {} ({})",
+            &commit.sha[..7],
+            commit.summary,
+            messages.lint_invalid
+        );
+    }
+    if invalid.is_empty() {
+        info!("{} ({})", messages.lint_passed, total);
+        return Ok(());
+    }
+    let err_msg = format!("{}: {}/{}", messages.lint_failed, invalid.len(), total);
+    error!("{}", err_msg);
+    Err(EaserError::Lint(err_msg).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Args, HttpArgs};
+    use easer::backend::Forge;
+    use easer::token::Token;
+    use mockito::{Matcher, Server};
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn default_args() -> Args {
+        Args {
+            owner: Some("test_owner".to_string()),
+            repo: Some("test_repo".to_string()),
+            token: Some(Token::new("test_token")),
+            token_file: None,
+            repo_path: ".".to_string(),
+            previous_tag: None,
+            tag_name: Some("v1.0.0".to_string()),
+            tag_prefix: "v".to_string(),
+            name: Some("Test Release".to_string()),
+            body: Some("This is a test release.".to_string()),
+            target_commitish: Some("main".to_string()),
+            draft: false,
+            prerelease: false,
+            lang: "zh-cn".to_string(),
+            artifacts: None,
+            auto_gen_notes: false,
+            no_auto_gen_notes: false,
+            checksums: false,
+            no_checksums: false,
+            sign: None,
+            sign_key: None,
+            api_url: None,
+            profile: None,
+            forge: Forge::Gitee,
+            no_preflight: true,
+            dry_run: false,
+            config: None,
+            all_or_nothing: false,
+            output: OutputFormat::Text,
+            no_ci_outputs: true,
+            interactive: false,
+            dotenv: easer::ci::DEFAULT_DOTENV.to_string(),
+            sections: easer::conventional::default_sections(),
+            repo_from_command_line: true,
+            http: HttpArgs::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_release_success() {
+        let mut server = Server::new_async().await;
+        let args = default_args();
+        let api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let mock = server
+            .mock("POST", api_path.as_str())
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": 1, "tag_name": "v1.0.0", "html_url": "http://example.com/release/v1.0.0"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_with_artifacts_success() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path1 = dir.path().join("artifact1.zip");
+        let file_path2 = dir.path().join("artifact2.txt");
+        File::create(&file_path1)
+            .unwrap()
+            .write_all(b"zip content")
+            .unwrap();
+        File::create(&file_path2)
+            .unwrap()
+            .write_all(b"text content")
+            .unwrap();
+
+        let args = Args {
+            artifacts: Some(vec![
+                file_path1.to_str().unwrap().to_string(),
+                file_path2.to_str().unwrap().to_string(),
+            ]),
+            ..default_args()
+        };
+        let release_api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+        let release_id = 123;
+
+        let tag = args.tag_name.clone().unwrap();
+        let release_mock = server
+            .mock("POST", release_api_path.as_str())
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"{{"id": {}, "tag_name": "{}", "html_url": "http://example.com/release/{}"}}"#,
+                release_id, tag, tag
+            ))
+            .create_async()
+            .await;
+
+        let upload_path = format!(
+            "/api/v5/repos/{}/{}/releases/{}/attach_files",
+            "test_owner", "test_repo", release_id
+        );
+
+        let upload_mock1 = server
+            .mock("POST", upload_path.as_str())
+            .match_header("Authorization", "token test_token")
+            .match_header("Accept", "application/json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{\"name\": \"artifact1.zip\", \"url\": \"...\"}"#)
+            .create_async()
+            .await;
+
+        let upload_mock2 = server
+            .mock("POST", upload_path.as_str())
+            .match_header("Authorization", "token test_token")
+            .match_header("Accept", "application/json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{\"name\": \"artifact2.txt\", \"url\": \"...\"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok(), "create_release failed: {:?}", result.err());
+        release_mock.assert_async().await;
+        upload_mock1.assert_async().await;
+        upload_mock2.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_on_forgejo() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("artifact.txt");
+        File::create(&file_path)
+            .unwrap()
+            .write_all(b"text content")
+            .unwrap();
+
+        let args = Args {
+            forge: Forge::Gitea,
+            prerelease: true,
+            artifacts: Some(vec![file_path.to_str().unwrap().to_string()]),
+            ..default_args()
+        };
+        let release_mock = server
+            .mock("POST", "/api/v1/repos/test_owner/test_repo/releases")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"tag_name": "v1.0.0", "prerelease": true}"#.to_string(),
+            ))
+            .with_status(201)
+            .with_body(r#"{"id": 5, "html_url": "http://example.com/release/v1.0.0"}"#)
+            .create_async()
+            .await;
+        let upload_mock = server
+            .mock(
+                "POST",
+                "/api/v1/repos/test_owner/test_repo/releases/5/assets",
+            )
+            .match_query(Matcher::UrlEncoded("name".into(), "artifact.txt".into()))
+            .match_header("Authorization", "token test_token")
+            .with_status(201)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&format!("{}/api/v1", server.url()))).await;
+
+        assert!(result.is_ok(), "create_release failed: {:?}", result.err());
+        release_mock.assert_async().await;
+        upload_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_all_or_nothing_rolls_back() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let token_path = dir.path().join("token");
+        std::fs::write(&token_path, "file_token").unwrap();
+        let config_path = dir.path().join(".easer.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                r#"
+[[targets]]
+forge = "gitee"
+owner = "acme"
+repo = "tool"
+api_url = "{url}"
+token_file = "{token}"
+
+[[targets]]
+forge = "github"
+owner = "acme"
+repo = "tool"
+api_url = "{url}"
+token_file = "{token}"
+"#,
+                url = server.url(),
+                token = token_path.display()
+            ),
+        )
+        .unwrap();
+
+        // An owner from the config file or EASER_OWNER does not replace the targets
+        let args = Args {
+            owner: Some("someone-else".to_string()),
+            repo: None,
+            token: None,
+            config: Some(config_path.to_str().unwrap().to_string()),
+            all_or_nothing: true,
+            repo_from_command_line: false,
+            ..default_args()
+        };
+        let gitee_mock = server
+            .mock("POST", "/api/v5/repos/acme/tool/releases")
+            .match_header("Authorization", "token file_token")
+            .with_status(201)
+            .with_body(r#"{"id": 11, "html_url": "https://gitee.com/acme/tool/releases/v1.0.0"}"#)
+            .create_async()
+            .await;
+        let github_mock = server
+            .mock("POST", "/repos/acme/tool/releases")
+            .match_header("Authorization", "Bearer file_token")
+            .with_status(422)
+            .with_body(r#"{"message": "Validation Failed"}"#)
+            .create_async()
+            .await;
+        let rollback_mock = server
+            .mock("DELETE", "/api/v5/repos/acme/tool/releases/11")
+            .with_status(204)
+            .create_async()
+            .await;
+        // The tag did not exist before, so the release created it
+        let _tags_mock = server
+            .mock("GET", "/api/v5/repos/acme/tool/tags")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body("[]")
+            .create_async()
+            .await;
+        let tag_rollback_mock = server
+            .mock("DELETE", "/api/v5/repos/acme/tool/tags/v1.0.0")
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let result = create_release(args, None).await;
+
+        assert!(result.unwrap_err().to_string().contains("1/2"));
+        gitee_mock.assert_async().await;
+        github_mock.assert_async().await;
+        rollback_mock.assert_async().await;
+        tag_rollback_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_with_checksums() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("artifact.txt");
+        File::create(&file_path)
+            .unwrap()
+            .write_all(b"hello\n")
+            .unwrap();
+
+        let args = Args {
+            artifacts: Some(vec![file_path.to_str().unwrap().to_string()]),
+            checksums: true,
+            ..default_args()
+        };
+        let sha256 = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+        let release_api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let release_mock = server
+            .mock("POST", release_api_path.as_str())
+            .match_body(Matcher::Regex(format!(
+                r"\| `artifact.txt` \| `{}` \|",
+                sha256
+            )))
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": 7, "tag_name": "v1.0.0"}"#)
+            .create_async()
+            .await;
+
+        let upload_path = format!(
+            "/api/v5/repos/{}/{}/releases/7/attach_files",
+            "test_owner", "test_repo"
+        );
+        let artifact_mock = server
+            .mock("POST", upload_path.as_str())
+            .match_body(Matcher::Regex("filename=\"artifact.txt\"".to_string()))
+            .with_status(200)
+            .create_async()
+            .await;
+        let manifest_mock = server
+            .mock("POST", upload_path.as_str())
+            .match_body(Matcher::Regex(format!(
+                "filename=\"SHA256SUMS\"[\\s\\S]*{}  artifact.txt",
+                sha256
+            )))
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok(), "create_release failed: {:?}", result.err());
+        release_mock.assert_async().await;
+        artifact_mock.assert_async().await;
+        manifest_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_artifact_display_name() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("easer");
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(64, 0);
+        File::create(&file_path).unwrap().write_all(&elf).unwrap();
+
+        let args = Args {
+            artifacts: Some(vec![format!(
+                "{}#easer-linux-x86_64",
+                file_path.to_str().unwrap()
+            )]),
+            ..default_args()
+        };
+        let release_api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+        let release_mock = server
+            .mock("POST", release_api_path.as_str())
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": 9, "tag_name": "v1.0.0"}"#)
+            .create_async()
+            .await;
+
+        let upload_path = format!(
+            "/api/v5/repos/{}/{}/releases/9/attach_files",
+            "test_owner", "test_repo"
+        );
+        let upload_mock = server
+            .mock("POST", upload_path.as_str())
+            .match_body(Matcher::Regex(
+                "filename=\"easer-linux-x86_64\"\r\nContent-Type: application/x-executable"
+                    .to_string(),
+            ))
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok(), "create_release failed: {:?}", result.err());
+        release_mock.assert_async().await;
+        upload_mock.assert_async().await;
+    }
+
+    async fn mock_preflight(server: &mut mockito::ServerGuard, tags: &str) -> Vec<mockito::Mock> {
+        let repo = "/api/v5/repos/test_owner/test_repo";
+        vec![
+            server
+                .mock("GET", "/api/v5/user")
+                .with_status(200)
+                .with_body(r#"{"login": "me"}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", repo)
+                .with_status(200)
+                .with_body(r#"{"full_name": "test_owner/test_repo", "permission": {"pull": true, "push": true, "admin": false}}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", format!("{}/commits/main", repo).as_str())
+                .with_status(200)
+                .with_body(r#"{"sha": "abc"}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", format!("{}/tags", repo).as_str())
+                .match_query(Matcher::Any)
+                .with_status(200)
+                .with_body(tags)
+                .create_async()
+                .await,
+            server
+                .mock("GET", format!("{}/releases/tags/v1.0.0", repo).as_str())
+                .with_status(200)
+                .with_body("null")
+                .create_async()
+                .await,
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_create_release_preflight_passes() {
+        let mut server = Server::new_async().await;
+        let args = Args {
+            no_preflight: false,
+            ..default_args()
+        };
+        let preflight_mocks = mock_preflight(&mut server, r#"[{"name": "v0.9.0"}]"#).await;
+        let release_mock = server
+            .mock("POST", "/api/v5/repos/test_owner/test_repo/releases")
+            .with_status(201)
+            .with_body(r#"{"id": 1, "tag_name": "v1.0.0"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok(), "create_release failed: {:?}", result.err());
+        for mock in preflight_mocks {
+            mock.assert_async().await;
+        }
+        release_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_preflight_blocks_existing_tag() {
+        let mut server = Server::new_async().await;
+        let args = Args {
+            no_preflight: false,
+            lang: "en-us".to_string(),
+            ..default_args()
+        };
+        let _preflight_mocks = mock_preflight(&mut server, r#"[{"name": "v1.0.0"}]"#).await;
+        let release_mock = server
+            .mock("POST", "/api/v5/repos/test_owner/test_repo/releases")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        let err = EaserError::from(result.unwrap_err());
+        assert!(err.to_string().contains("Pre-flight checks failed: 1/6"));
+        assert_eq!(err.kind(), ErrorKind::TagConflict);
+        assert_eq!(err.exit_code(), 8);
+        release_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_dry_run_makes_no_requests() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("artifact.zip");
+        File::create(&file_path)
+            .unwrap()
+            .write_all(b"zip content")
+            .unwrap();
+        let args = Args {
+            token: None,
+            dry_run: true,
+            no_preflight: false,
+            checksums: true,
+            artifacts: Some(vec![file_path.to_str().unwrap().to_string()]),
+            ..default_args()
+        };
+        let mock = server
+            .mock("POST", Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok(), "dry run failed: {:?}", result.err());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_success_no_html_url() {
+        let mut server = Server::new_async().await;
+        let args = default_args();
+        let api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let mock = server
+            .mock("POST", api_path.as_str())
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": 1, "tag_name": "v1.0.0"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_partial_upload() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("artifact.zip");
+        std::fs::write(&file_path, "zip content").unwrap();
+        let args = Args {
+            lang: "en-us".to_string(),
+            artifacts: Some(vec![file_path.to_str().unwrap().to_string()]),
+            ..default_args()
+        };
+        let release_mock = server
+            .mock("POST", "/api/v5/repos/test_owner/test_repo/releases")
+            .with_status(201)
+            .with_body(r#"{"id": 5, "tag_name": "v1.0.0"}"#)
+            .create_async()
+            .await;
+        let upload_mock = server
+            .mock(
+                "POST",
+                "/api/v5/repos/test_owner/test_repo/releases/5/attach_files",
+            )
+            .with_status(500)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        let err = EaserError::from(result.unwrap_err());
+        assert_eq!(err.to_string(), "1/1 uploads failed");
+        assert_eq!(err.exit_code(), 12);
+        release_mock.assert_async().await;
+        upload_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_api_error() {
+        let mut server = Server::new_async().await;
+        let args = default_args();
+        let api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let mock = server
+            .mock("POST", api_path.as_str())
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message": "Unauthorized"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        let err = EaserError::from(result.unwrap_err());
+        assert!(err
+            .to_string()
+            .contains("API 请求失败，状态码: 401 Unauthorized - {\"message\": \"Unauthorized\"}"));
+        assert_eq!(err.kind(), ErrorKind::Auth);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_api_error_redacts_token() {
+        let mut server = Server::new_async().await;
+        let args = Args {
+            lang: "en-us".to_string(),
+            ..default_args()
+        };
+        let api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let mock = server
+            .mock("POST", api_path.as_str())
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message": "invalid access_token test_token"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("invalid access_token ***"));
+        assert!(!err.contains("test_token"));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_invalid_tag() {
+        let args = Args {
+            tag_name: Some("invalid-tag".to_string()),
+            ..default_args()
+        };
+
+        let result = create_release(args, None).await;
+
+        let err = EaserError::from(result.unwrap_err());
+        assert!(err
+            .to_string()
+            .contains("无效的语义化版本标签名称: invalid-tag"));
+        assert_eq!(err.kind(), ErrorKind::InvalidVersion);
+    }
+
+    #[tokio::test]
+    async fn test_create_release_success_en_us() {
+        let mut server = Server::new_async().await;
+        let args = Args {
+            lang: "en-us".to_string(),
+            ..default_args()
+        };
+        let api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let mock = server
+            .mock("POST", api_path.as_str())
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": 1, "tag_name": "v1.0.0", "html_url": "http://example.com/release/v1.0.0"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_create_release_api_error_en_us() {
+        let mut server = Server::new_async().await;
+        let args = Args {
+            lang: "en-us".to_string(),
+            ..default_args()
+        };
+        let api_path = format!("/api/v5/repos/{}/{}/releases", "test_owner", "test_repo");
+
+        let mock = server
+            .mock("POST", api_path.as_str())
+            .with_status(404)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message": "Not Found"}"#)
+            .create_async()
+            .await;
+
+        let result = create_release(args, Some(&server.url())).await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains(
+            "API request failed with status: 404 Not Found - {\"message\": \"Not Found\"}"
+        ));
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,130 @@
+use crate::args::Cli;
+use clap::CommandFactory;
+use clap_complete::env::Shells;
+use clap_complete::{CompletionCandidate, Shell};
+use easer::conventional;
+use git2::Repository;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Variable the shell sets when it calls back into easer for candidates
+const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Answer a completion request from the shell and exit, if this is one
+pub fn handle_request() {
+    clap_complete::CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+/// `easer completions <shell>`: the script that registers easer with the
+/// shell. Candidates are computed by easer itself on every completion, so
+/// `--previous-tag` offers the tags of the repository being released
+pub fn print_completions(shell: Shell) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .ok_or_else(|| format!("Unsupported shell {}", name))?;
+    let bin = Cli::command().get_name().to_string();
+    completer.write_registration(COMPLETE_VAR, &bin, &bin, &bin, &mut std::io::stdout())?;
+    Ok(())
+}
+
+/// `easer man`: the page for `easer` on stdout, or one page per subcommand
+/// (`easer.1`, `easer-release.1`, ...) written to `out_dir`
+pub fn print_man(out_dir: Option<&str>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let cmd = Cli::command();
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(cmd, dir)?;
+        }
+        None => clap_mangen::Man::new(cmd).render(&mut std::io::stdout())?,
+    }
+    Ok(())
+}
+
+/// Value of `--name value` or `--name=value` on the command line being completed
+fn option_value(args: &[String], name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let mut value = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if *arg == flag {
+            value = args.next().cloned();
+        } else if let Some(rest) = arg.strip_prefix(&flag).and_then(|s| s.strip_prefix('=')) {
+            value = Some(rest.to_string());
+        }
+    }
+    value
+}
+
+/// Version tags of the repository, newest first
+fn semver_tags(repo_path: &Path, tag_prefix: &str) -> Vec<String> {
+    let Ok(repo) = Repository::open(repo_path) else {
+        return Vec::new();
+    };
+    let tags = conventional::version_tags(&repo, tag_prefix).unwrap_or_default();
+    tags.into_iter().rev().map(|(_, tag)| tag).collect()
+}
+
+/// `--previous-tag` candidates: the version tags in `--repo-path`, using
+/// `--tag-prefix` when given on the command line being completed
+pub fn complete_previous_tag(current: &OsStr) -> Vec<CompletionCandidate> {
+    let args: Vec<String> = std::env::args().collect();
+    let repo_path = option_value(&args, "repo-path").unwrap_or_else(|| ".".to_string());
+    let tag_prefix = option_value(&args, "tag-prefix")
+        .or_else(|| std::env::var("EASER_TAG_PREFIX").ok())
+        .unwrap_or_else(|| "v".to_string());
+    let current = current.to_string_lossy();
+    semver_tags(Path::new(&repo_path), &tag_prefix)
+        .into_iter()
+        .filter(|tag| tag.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_option_value() {
+        let args: Vec<String> = ["easer", "--", "easer", "changelog", "--repo-path", "../app"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(option_value(&args, "repo-path").as_deref(), Some("../app"));
+        assert_eq!(option_value(&args, "tag-prefix"), None);
+
+        let args = vec!["--tag-prefix=release-".to_string()];
+        assert_eq!(
+            option_value(&args, "tag-prefix").as_deref(),
+            Some("release-")
+        );
+    }
+
+    #[test]
+    fn test_semver_tags_newest_first() {
+        let dir = tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let sig = git2::Signature::now("t", "t@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        let object = repo.find_object(commit, None).unwrap();
+        for tag in ["v0.9.0", "v0.10.0", "nightly", "1.0.0"] {
+            repo.tag_lightweight(tag, &object, false).unwrap();
+        }
+
+        assert_eq!(semver_tags(dir.path(), "v"), ["v0.10.0", "v0.9.0"]);
+        assert_eq!(semver_tags(dir.path(), ""), ["1.0.0"]);
+        assert!(semver_tags(&dir.path().join("missing"), "v").is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+use crate::args::Args;
+use crate::profile;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, ValueEnum};
+use easer::backend::Forge;
+use easer::conventional::Section;
+use easer::error::EaserError;
+use easer::sign::SignMethod;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Project configuration, looked up in the repository root
+pub const CONFIG_FILE: &str = ".easer.toml";
+
+/// Personal defaults, in the same directory as the `easer login` credentials
+pub const USER_CONFIG_FILE: &str = "config.toml";
+
+/// Defaults for command-line settings, from the project or the user config
+#[derive(Deserialize, Default, Debug, Clone)]
+pub struct Settings {
+    pub owner: Option<String>,
+    pub repo: Option<String>,
+    pub forge: Option<Forge>,
+    pub api_url: Option<String>,
+    pub lang: Option<String>,
+    pub target_commitish: Option<String>,
+    pub tag_prefix: Option<String>,
+    /// Artifact paths or glob patterns
+    pub artifacts: Option<Vec<String>>,
+    pub auto_gen_notes: Option<bool>,
+    pub checksums: Option<bool>,
+    pub sign: Option<SignMethod>,
+    pub sign_key: Option<String>,
+    /// Release-note sections, in order
+    pub sections: Option<Vec<Section>>,
+}
+
+/// Contents of `.easer.toml`
+#[derive(Deserialize, Default, Debug)]
+pub struct Config {
+    #[serde(flatten)]
+    pub settings: Settings,
+    /// Repositories every release is published to
+    #[serde(default)]
+    pub targets: Vec<TargetConfig>,
+}
+
+/// One `[[targets]]` entry
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct TargetConfig {
+    pub forge: Forge,
+    pub owner: String,
+    pub repo: String,
+    pub api_url: Option<String>,
+    /// Environment variable holding this target's token, defaults to the forge's
+    pub token_env: Option<String>,
+    pub token_file: Option<String>,
+}
+
+/// `--config` if given, otherwise `.easer.toml` in the repository
+pub fn config_path(config: Option<&str>, repo_path: &str) -> PathBuf {
+    match config {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(repo_path).join(CONFIG_FILE),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/easer/config.toml`, falling back to `~/.config/easer/config.toml`
+pub fn user_config_path() -> Option<PathBuf> {
+    profile::config_dir().map(|dir| dir.join(USER_CONFIG_FILE))
+}
+
+/// Read a TOML file, treating a missing file as empty
+fn load_toml<T: serde::de::DeserializeOwned + Default>(
+    path: &Path,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content).map_err(|e| {
+            EaserError::Config(format!("Invalid config file {}: {}", path.display(), e)).into()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => {
+            Err(EaserError::Config(format!("Failed to read {}: {}", path.display(), e)).into())
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file, treating a missing file as empty
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        load_toml(path)
+    }
+}
+
+/// Where the effective value of a setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    UserConfig(PathBuf),
+    ProjectConfig(PathBuf),
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::UserConfig(path) => write!(f, "user config {}", path.display()),
+            Source::ProjectConfig(path) => write!(f, "project config {}", path.display()),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// The config files below the environment and the command line, highest
+/// precedence first: the project config overrides personal defaults
+pub struct Layers {
+    files: Vec<(Source, Settings)>,
+}
+
+impl Layers {
+    pub fn load(project: &Path, user: Option<&Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut files = vec![(
+            Source::ProjectConfig(project.to_path_buf()),
+            Config::load(project)?.settings,
+        )];
+        if let Some(user) = user {
+            files.push((Source::UserConfig(user.to_path_buf()), load_toml(user)?));
+        }
+        Ok(Layers { files })
+    }
+
+    /// The value from the first config file that sets it
+    fn lookup<T>(&self, get: impl Fn(&Settings) -> Option<T>) -> Option<(T, Source)> {
+        self.files
+            .iter()
+            .find_map(|(source, settings)| get(settings).map(|value| (value, source.clone())))
+    }
+}
+
+/// One line of `easer config show`
+pub struct Resolved {
+    pub key: &'static str,
+    pub value: String,
+    pub source: Source,
+}
+
+/// Keep `field` if it was given on the command line or in its environment
+/// variable, otherwise take the configured value if there is one
+fn layer<T>(explicit: Option<Source>, configured: Option<(T, Source)>, field: &mut T) -> Source {
+    if let Some(source) = explicit {
+        return source;
+    }
+    match configured {
+        Some((value, source)) => {
+            *field = value;
+            source
+        }
+        None => Source::Default,
+    }
+}
+
+fn show_enum<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Fill in every setting the command line and environment left unset from the
+/// config files, recording where each effective value came from
+pub fn apply_layers(args: &mut Args, matches: &ArgMatches, layers: &Layers) -> Vec<Resolved> {
+    let command = Args::command();
+    // `--no-checksums` and `--no-auto-gen-notes` win over the flag itself, its
+    // variable and the config files
+    if args.no_auto_gen_notes {
+        args.auto_gen_notes = false;
+    }
+    if args.no_checksums {
+        args.checksums = false;
+    }
+    let negated = |id: &str| {
+        let negation = format!("no_{}", id);
+        command
+            .get_arguments()
+            .any(|arg| arg.get_id() == negation.as_str())
+            && matches.value_source(&negation) == Some(ValueSource::CommandLine)
+    };
+    // Settings without a flag, like `sections`, only come from config files
+    let explicit = |id: &str| {
+        if negated(id) {
+            return Some(Source::CommandLine);
+        }
+        let arg = command.get_arguments().find(|arg| arg.get_id() == id)?;
+        match matches.value_source(id)? {
+            ValueSource::CommandLine => Some(Source::CommandLine),
+            ValueSource::EnvVariable => arg
+                .get_env()
+                .map(|env| Source::Env(env.to_string_lossy().to_string())),
+            _ => None,
+        }
+    };
+    args.repo_from_command_line =
+        [explicit("owner"), explicit("repo")].contains(&Some(Source::CommandLine));
+    let mut resolved = Vec::new();
+    macro_rules! setting {
+        ($field:ident, $get:expr, $show:expr) => {{
+            let key = stringify!($field);
+            let source = layer(explicit(key), layers.lookup($get), &mut args.$field);
+            resolved.push(Resolved {
+                key,
+                value: $show(&args.$field),
+                source,
+            });
+        }};
+    }
+    let show_str = |v: &Option<String>| v.clone().unwrap_or_default();
+
+    setting!(owner, |s| s.owner.clone().map(Some), show_str);
+    setting!(repo, |s| s.repo.clone().map(Some), show_str);
+    setting!(forge, |s| s.forge, show_enum);
+    setting!(api_url, |s| s.api_url.clone().map(Some), show_str);
+    setting!(lang, |s| s.lang.clone(), String::clone);
+    setting!(
+        target_commitish,
+        |s| s.target_commitish.clone().map(Some),
+        show_str
+    );
+    setting!(tag_prefix, |s| s.tag_prefix.clone(), String::clone);
+    setting!(
+        artifacts,
+        |s| s.artifacts.clone().map(Some),
+        |v: &Option<Vec<String>>| v.as_deref().unwrap_or_default().join(",")
+    );
+    setting!(auto_gen_notes, |s| s.auto_gen_notes, bool::to_string);
+    setting!(checksums, |s| s.checksums, bool::to_string);
+    setting!(sign, |s| s.sign.map(Some), |v: &Option<SignMethod>| v
+        .as_ref()
+        .map(show_enum)
+        .unwrap_or_default());
+    setting!(sign_key, |s| s.sign_key.clone().map(Some), show_str);
+    setting!(sections, |s| s.sections.clone(), |v: &Vec<Section>| {
+        v.iter()
+            .map(|section| format!("{}={}", section.types.join("|"), section.title))
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    resolved
+}
+
+/// Layer the config files under arguments parsed from `matches`, which tell
+/// flags given explicitly apart from clap defaults
+pub fn layer_args(
+    args: &mut Args,
+    matches: &ArgMatches,
+) -> Result<Vec<Resolved>, Box<dyn Error + Send + Sync>> {
+    let project = config_path(args.config.as_deref(), &args.repo_path);
+    let layers = Layers::load(&project, user_config_path().as_deref())?;
+    Ok(apply_layers(args, matches, &layers))
+}
+
+/// `easer config show`: print every layered setting, its value and its source
+pub fn show(resolved: &[Resolved]) {
+    let width = resolved.iter().map(|r| r.key.len()).max().unwrap_or(0);
+    for r in resolved {
+        let value = if r.value.is_empty() { "-" } else { &r.value };
+        println!("{:width$}  {}  ({})", r.key, value, r.source, width = width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::FromArgMatches;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_targets() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(
+            &path,
+            r#"
+[[targets]]
+forge = "gitee"
+owner = "acme"
+repo = "tool"
+
+[[targets]]
+forge = "forgejo"
+owner = "infra"
+repo = "tool"
+api_url = "https://git.example.com"
+token_env = "MIRROR_TOKEN"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let missing = Config::load(&dir.path().join("missing.toml")).unwrap();
+
+        assert_eq!(config.targets.len(), 2);
+        assert_eq!(config.targets[1].forge, Forge::Gitea);
+        assert_eq!(config.targets[1].token_env.as_deref(), Some("MIRROR_TOKEN"));
+        assert!(missing.targets.is_empty());
+    }
+
+    #[test]
+    fn test_layered_settings() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join(CONFIG_FILE);
+        let user = dir.path().join(USER_CONFIG_FILE);
+        std::fs::write(
+            &project,
+            r#"
+owner = "acme"
+lang = "en-us"
+tag_prefix = "release-"
+
+[[sections]]
+title = "Performance"
+types = ["perf"]
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &user,
+            "lang = \"zh-cn\"\nrepo = \"tool\"\nchecksums = true\nsign_key = \"keys/release\"\n",
+        )
+        .unwrap();
+
+        let matches = Args::command().get_matches_from(["easer", "--owner", "me", "--sign", "ssh"]);
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        let layers = Layers::load(&project, Some(&user)).unwrap();
+        let resolved = apply_layers(&mut args, &matches, &layers);
+        let source = |key: &str| &resolved.iter().find(|r| r.key == key).unwrap().source;
+
+        assert_eq!(args.owner.as_deref(), Some("me"));
+        assert_eq!(source("owner"), &Source::CommandLine);
+        assert!(args.repo_from_command_line);
+        assert_eq!(args.repo.as_deref(), Some("tool"));
+        assert_eq!(source("repo"), &Source::UserConfig(user.clone()));
+        assert_eq!(args.lang, "en-us");
+        assert_eq!(source("lang"), &Source::ProjectConfig(project.clone()));
+        assert_eq!(args.tag_prefix, "release-");
+        assert!(args.checksums);
+        assert_eq!(args.sign, Some(SignMethod::Ssh));
+        assert_eq!(args.sign_key.as_deref(), Some("keys/release"));
+        assert_eq!(source("sign_key"), &Source::UserConfig(user.clone()));
+        assert_eq!(args.sections[0].types, vec!["perf".to_string()]);
+        assert_eq!(args.forge, Forge::Gitee);
+        assert_eq!(source("forge"), &Source::Default);
+
+        let matches = Args::command().get_matches_from(["easer", "--no-checksums"]);
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        let resolved = apply_layers(&mut args, &matches, &layers);
+        let source = |key: &str| &resolved.iter().find(|r| r.key == key).unwrap().source;
+        assert!(!args.checksums);
+        assert_eq!(source("checksums"), &Source::CommandLine);
+        // `repo` comes from the user config, so `[[targets]]` still apply
+        assert_eq!(args.repo.as_deref(), Some("tool"));
+        assert!(!args.repo_from_command_line);
+
+        let matches = Args::command().get_matches_from(["easer", "--no-checksums", "--checksums"]);
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        apply_layers(&mut args, &matches, &layers);
+        assert!(!args.checksums);
+    }
+}
@@ -1,7 +1,255 @@
+use crate::error::EaserError;
+use crate::remote::{RemoteUrl, DEFAULT_REMOTE};
 use git2::{Cred, FetchOptions, RemoteCallbacks, Repository, Sort};
 use semver::Version;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
+use tracing::{debug, instrument};
 
+/// A heading in the release notes and the commit types listed under it
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub types: Vec<String>,
+}
+
+/// Features and bug fixes, the sections used unless the config lists its own
+pub fn default_sections() -> Vec<Section> {
+    vec![
+        Section {
+            title: "✨ Features".to_string(),
+            types: vec!["feat".to_string()],
+        },
+        Section {
+            title: "🐛 Bug Fixes".to_string(),
+            types: vec!["fix".to_string()],
+        },
+    ]
+}
+
+/// Type of a Conventional Commits summary, e.g. `feat` for `feat(api)!: ...`
+fn commit_type(summary: &str) -> &str {
+    summary
+        .split(['(', '!', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+}
+
+/// Does the summary follow `type(scope)!: description`?
+fn is_conventional(summary: &str) -> bool {
+    let Some((head, description)) = summary.split_once(':') else {
+        return false;
+    };
+    let head = head.strip_suffix('!').unwrap_or(head);
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, rest)) => match rest.strip_suffix(')') {
+            Some(scope) if !scope.is_empty() && !scope.contains(['(', ')']) => (kind, Some(scope)),
+            _ => return false,
+        },
+        None => (head, None),
+    };
+    !kind.is_empty()
+        && kind.chars().all(|c| c.is_ascii_alphanumeric())
+        && scope.is_none_or(|scope| !scope.trim().is_empty())
+        && description.starts_with(' ')
+        && !description.trim().is_empty()
+}
+
+fn is_breaking(summary: &str) -> bool {
+    summary.contains("BREAKING CHANGE") || summary.contains("!:")
+}
+
+/// Version component the commits since the last release call for
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    /// Nothing releasable: no breaking change and no commit type of any section
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// Breaking changes bump the major version, features the minor version and
+    /// any other commit type listed in `sections` the patch version
+    pub fn of(commits: &[Commit], sections: &[Section]) -> Bump {
+        commits
+            .iter()
+            .map(|commit| {
+                let kind = commit_type(&commit.summary);
+                if is_breaking(&commit.summary) {
+                    Bump::Major
+                } else if kind == "feat" {
+                    Bump::Minor
+                } else if sections.iter().any(|s| s.types.iter().any(|t| t == kind)) {
+                    Bump::Patch
+                } else {
+                    Bump::None
+                }
+            })
+            .max()
+            .unwrap_or(Bump::None)
+    }
+
+    pub fn apply(self, version: &Version) -> Version {
+        let mut next = version.clone();
+        match self {
+            Bump::Major => {
+                next.major += 1;
+                next.minor = 0;
+                next.patch = 0;
+            }
+            Bump::Minor => {
+                next.minor += 1;
+                next.patch = 0;
+            }
+            Bump::Patch => next.patch += 1,
+            Bump::None => {}
+        }
+        next
+    }
+}
+
+/// A commit between the base tag and the release target
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub sha: String,
+    pub summary: String,
+    pub is_merge: bool,
+}
+
+/// The version tag a release builds on and the commits made since, oldest first
+pub struct History {
+    pub base_version: Version,
+    /// Empty when the repository has no version tag yet
+    pub base_tag: String,
+    pub commits: Vec<Commit>,
+}
+
+/// Tags that are `tag_prefix` followed by a semantic version, oldest first
+pub fn version_tags(
+    repo: &Repository,
+    tag_prefix: &str,
+) -> Result<Vec<(Version, String)>, Box<dyn Error + Send + Sync>> {
+    let tag_names = repo.tag_names(None)?;
+    let mut versions = vec![];
+    for name in tag_names.iter().flatten() {
+        if let Some(stripped) = name.strip_prefix(tag_prefix) {
+            if let Ok(ver) = Version::parse(stripped) {
+                versions.push((ver, name.to_string()));
+            }
+        }
+    }
+    versions.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(versions)
+}
+
+/// Find the latest version tag (or `previous_tag`) and walk the commits from
+/// there to `target`, which may be a branch, tag or commit SHA
+pub fn collect_history(
+    repo: &Repository,
+    previous_tag: Option<&String>,
+    target: &str,
+    tag_prefix: &str,
+) -> Result<History, Box<dyn Error + Send + Sync>> {
+    let mut versions = version_tags(repo, tag_prefix)?;
+    // Determine base version
+    let (base_version, base_tag) = if let Some(prev) = previous_tag {
+        if let Some((ver, tag)) = versions.iter().find(|(_, t)| t == prev) {
+            (ver.clone(), tag.clone())
+        } else {
+            return Err(EaserError::Git(format!("Previous tag {} not found", prev)).into());
+        }
+    } else if versions.is_empty() {
+        (Version::new(0, 0, 0), String::new())
+    } else {
+        versions.pop().unwrap()
+    };
+
+    // Walk commits since base_tag to target
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(repo.revparse_single(target)?.peel_to_commit()?.id())?;
+    if !base_tag.is_empty() {
+        revwalk.hide(repo.revparse_single(&base_tag)?.peel_to_commit()?.id())?;
+    }
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+    let mut commits = Vec::new();
+    for oid_res in revwalk {
+        let oid = oid_res?;
+        let commit = repo.find_commit(oid)?;
+        commits.push(Commit {
+            sha: oid.to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            is_merge: commit.parent_count() > 1,
+        });
+    }
+    Ok(History {
+        base_version,
+        base_tag,
+        commits,
+    })
+}
+
+/// Commits since the base tag whose summary does not follow Conventional
+/// Commits. Merge commits are skipped
+pub fn lint_commits(
+    repo_path: &str,
+    previous_tag: Option<&String>,
+    target: &str,
+    tag_prefix: &str,
+) -> Result<(usize, Vec<Commit>), Box<dyn Error + Send + Sync>> {
+    let repo = Repository::open(repo_path)?;
+    let history = collect_history(&repo, previous_tag, target, tag_prefix)?;
+    let total = history.commits.len();
+    let invalid = history
+        .commits
+        .into_iter()
+        .filter(|commit| !commit.is_merge && !is_conventional(&commit.summary))
+        .collect();
+    Ok((total, invalid))
+}
+
+/// What `easer next-version` reports
+#[derive(Serialize, Debug)]
+pub struct NextVersion {
+    pub version: String,
+    pub tag: String,
+    pub bump: Bump,
+    pub base_tag: Option<String>,
+    pub commits: usize,
+}
+
+/// Work out the next version from the local tags and commits only. Unlike
+/// `generate_release_info` nothing is fetched, and with nothing releasable
+/// the bump is `None` and the version stays at the base tag's
+pub fn next_version(
+    repo_path: &str,
+    previous_tag: Option<&String>,
+    target: &str,
+    manual_version: Option<&str>,
+    tag_prefix: &str,
+    sections: &[Section],
+) -> Result<NextVersion, Box<dyn Error + Send + Sync>> {
+    let repo = Repository::open(repo_path)?;
+    let history = collect_history(&repo, previous_tag, target, tag_prefix)?;
+    let bump = Bump::of(&history.commits, sections);
+    let version = match manual_version {
+        Some(ver_str) => Version::parse(ver_str.strip_prefix(tag_prefix).unwrap_or(ver_str))?,
+        None => bump.apply(&history.base_version),
+    };
+    Ok(NextVersion {
+        tag: format!("{}{}", tag_prefix, version),
+        version: version.to_string(),
+        bump,
+        base_tag: Some(history.base_tag).filter(|tag| !tag.is_empty()),
+        commits: history.commits.len(),
+    })
+}
+
+#[derive(Debug, Clone)]
 pub struct ReleaseInfo {
     pub tag_name: String,
     pub name: String,
@@ -14,7 +262,10 @@ pub fn generate_release_info(
     previous_tag: Option<&String>,
     target: &str,
     manual_version: Option<&str>,
-) -> Result<ReleaseInfo, Box<dyn Error>> {
+    commit_url_prefix: Option<&str>,
+    tag_prefix: &str,
+    sections: &[Section],
+) -> Result<ReleaseInfo, Box<dyn Error + Send + Sync>> {
     let repo = Repository::open(repo_path)?;
 
     let mut callbacks = RemoteCallbacks::new();
@@ -32,104 +283,58 @@ pub fn generate_release_info(
     let mut fo = FetchOptions::new();
     fo.remote_callbacks(callbacks);
 
-    let mut remote = repo.find_remote("origin")?;
+    let mut remote = repo.find_remote(DEFAULT_REMOTE)?;
+    debug!("Fetching tags from {}", DEFAULT_REMOTE);
     remote.fetch(&["refs/tags/*:refs/tags/*"], Some(&mut fo), None)?;
 
-    // Prepare base URL for commit links
+    // Prepare the commit link prefix, preferring the forge the release goes to.
+    // Parsing the remote also keeps credentials embedded in its URL out of the notes
     let origin_url = remote.url().unwrap_or("").to_string();
-    let base_url = if origin_url.starts_with("git@") {
-        if let Some((host, path)) = origin_url.trim_start_matches("git@").split_once(':') {
-            format!("https://{}/{}", host, path.trim_end_matches(".git"))
-        } else {
-            origin_url.clone()
-        }
-    } else if origin_url.starts_with("http") {
-        origin_url.trim_end_matches(".git").to_string()
-    } else {
-        origin_url.clone()
+    let base_url = match commit_url_prefix {
+        Some(prefix) => prefix.to_string(),
+        None => match RemoteUrl::parse(&origin_url) {
+            Some(remote_url) => format!("{}/commit", remote_url.web_url()),
+            None => format!("{}/commit", origin_url.trim_end_matches(".git")),
+        },
     };
 
-    // Collect semver tags
-    let tag_names = repo.tag_names(None)?;
-    let mut versions = vec![];
-    for name_opt in tag_names.iter() {
-        if let Some(name) = name_opt {
-            if let Some(stripped) = name.strip_prefix('v') {
-                if let Ok(ver) = Version::parse(stripped) {
-                    versions.push((ver, name.to_string()));
-                }
-            }
-        }
-    }
-    versions.sort_by(|a, b| a.0.cmp(&b.0));
-    // Determine base version
-    let (base_version, base_tag) = if let Some(prev) = previous_tag {
-        if let Some((ver, tag)) = versions.iter().find(|(_, t)| t == prev) {
-            (ver.clone(), tag.clone())
-        } else {
-            return Err(format!("Previous tag {} not found", prev).into());
-        }
-    } else if versions.is_empty() {
-        (Version::new(0, 0, 0), String::new())
-    } else {
-        versions.pop().unwrap()
-    };
-
-    // Walk commits since base_tag to target
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_range(&format!("{}..{}", base_tag, target))?;
-    if base_tag.is_empty() {
-        revwalk.push_ref(target)?;
-    } else {
-        revwalk.push_range(&format!("{}..{}", base_tag, target))?;
-    }
-    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    let history = collect_history(&repo, previous_tag, target, tag_prefix)?;
+    // A release is cut even without feature or fix commits, as a patch
+    let bump = Bump::of(&history.commits, sections).max(Bump::Patch);
+    debug!(
+        base_tag = history.base_tag.as_str(),
+        commits = history.commits.len(),
+        "Bumping {:?} version",
+        bump
+    );
 
-    let mut bump_major = false;
-    let mut bump_minor = false;
-    let mut features: Vec<(String, String)> = Vec::new();
-    let mut fixes: Vec<(String, String)> = Vec::new();
     let mut breaking: Vec<(String, String)> = Vec::new();
+    let mut grouped: Vec<Vec<(String, String)>> = vec![Vec::new(); sections.len()];
 
-    for oid_res in revwalk {
-        let oid = oid_res?;
-        let commit = repo.find_commit(oid)?;
-        let msg = commit.summary().unwrap_or("").to_string();
-        let sha = oid.to_string();
+    for commit in history.commits {
+        let (msg, sha) = (commit.summary, commit.sha);
         // Categorize commit
-        if msg.contains("BREAKING CHANGE") || msg.contains("!:") {
-            breaking.push((msg.clone(), sha.clone()));
-            bump_major = true;
-        } else if msg.starts_with("feat") {
-            features.push((msg.clone(), sha.clone()));
-            if !bump_major {
-                bump_minor = true;
-            }
-        } else if msg.starts_with("fix") {
-            fixes.push((msg.clone(), sha.clone()));
+        if is_breaking(&msg) {
+            breaking.push((msg, sha));
+            continue;
+        }
+        let kind = commit_type(&msg);
+        if let Some(i) = sections
+            .iter()
+            .position(|section| section.types.iter().any(|t| t == kind))
+        {
+            grouped[i].push((msg, sha));
         }
     }
     // Bump version or use manual version
-    let next = if let Some(ver_str) = manual_version {
+    let next = match manual_version {
         // Supports "v0.2.1" or "0.2.1"
-        Version::parse(ver_str.trim_start_matches('v'))?
-    } else {
-        let mut v = base_version.clone();
-        if bump_major {
-            v.major += 1;
-            v.minor = 0;
-            v.patch = 0;
-        } else if bump_minor {
-            v.minor += 1;
-            v.patch = 0;
-        } else {
-            v.patch += 1;
-        }
-        v
+        Some(ver_str) => Version::parse(ver_str.strip_prefix(tag_prefix).unwrap_or(ver_str))?,
+        None => bump.apply(&history.base_version),
     };
 
     // Construct tag and name
-    let tag_name = format!("v{}", next);
+    let tag_name = format!("{}{}", tag_prefix, next);
     let name = format!("Release {}", next);
     // Build changelog body
     let mut body = String::new();
@@ -137,32 +342,18 @@ pub fn generate_release_info(
         body.push_str("## ⚠ BREAKING CHANGES\n");
         for (msg, sha) in breaking {
             let short = &sha[..7];
-            body.push_str(&format!(
-                "- {} ([{}]({}/commit/{}))\n",
-                msg, short, base_url, sha
-            ));
+            body.push_str(&format!("- {} ([{}]({}/{}))\n", msg, short, base_url, sha));
         }
         body.push('\n');
     }
-    if !features.is_empty() {
-        body.push_str("## ✨ Features\n");
-        for (msg, sha) in features {
-            let short = &sha[..7];
-            body.push_str(&format!(
-                "- {} ([{}]({}/commit/{}))\n",
-                msg, short, base_url, sha
-            ));
+    for (section, commits) in sections.iter().zip(grouped) {
+        if commits.is_empty() {
+            continue;
         }
-        body.push('\n');
-    }
-    if !fixes.is_empty() {
-        body.push_str("## 🐛 Bug Fixes\n");
-        for (msg, sha) in fixes {
+        body.push_str(&format!("## {}\n", section.title));
+        for (msg, sha) in commits {
             let short = &sha[..7];
-            body.push_str(&format!(
-                "- {} ([{}]({}/commit/{}))\n",
-                msg, short, base_url, sha
-            ));
+            body.push_str(&format!("- {} ([{}]({}/{}))\n", msg, short, base_url, sha));
         }
         body.push('\n');
     }
@@ -172,3 +363,198 @@ pub fn generate_release_info(
         body,
     })
 }
+
+/// Release notes, next version and commit lint for one repository, built up
+/// with chained setters
+///
+/// ```no_run
+/// # fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// use easer::ChangelogGenerator;
+///
+/// let notes = ChangelogGenerator::new(".").previous_tag("v1.2.0").generate()?;
+/// println!("{}\n\n{}", notes.name, notes.body);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChangelogGenerator {
+    repo_path: String,
+    previous_tag: Option<String>,
+    target: String,
+    version: Option<String>,
+    commit_url_prefix: Option<String>,
+    tag_prefix: String,
+    sections: Vec<Section>,
+}
+
+impl ChangelogGenerator {
+    /// Commits up to `HEAD` since the latest version tag, with `v` tags and
+    /// the default sections
+    pub fn new(repo_path: impl Into<String>) -> Self {
+        ChangelogGenerator {
+            repo_path: repo_path.into(),
+            previous_tag: None,
+            target: "HEAD".to_string(),
+            version: None,
+            commit_url_prefix: None,
+            tag_prefix: "v".to_string(),
+            sections: default_sections(),
+        }
+    }
+
+    /// Tag to start from instead of the latest version tag
+    pub fn previous_tag(mut self, previous_tag: impl Into<String>) -> Self {
+        self.previous_tag = Some(previous_tag.into());
+        self
+    }
+
+    /// Branch, tag or commit SHA to stop at
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    /// Use this version (with or without the tag prefix) instead of bumping
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Prefix commit SHAs are appended to for links. Default: the `origin`
+    /// remote's web URL
+    pub fn commit_url_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.commit_url_prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn tag_prefix(mut self, tag_prefix: impl Into<String>) -> Self {
+        self.tag_prefix = tag_prefix.into();
+        self
+    }
+
+    pub fn sections(mut self, sections: Vec<Section>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Fetch tags from `origin`, then render tag, name and notes of the next
+    /// release
+    #[instrument(
+        name = "changelog",
+        skip_all,
+        fields(repo = %self.repo_path, target = %self.target, previous_tag = self.previous_tag.as_deref())
+    )]
+    pub fn generate(&self) -> Result<ReleaseInfo, EaserError> {
+        Ok(generate_release_info(
+            &self.repo_path,
+            self.previous_tag.as_ref(),
+            &self.target,
+            self.version.as_deref(),
+            self.commit_url_prefix.as_deref(),
+            &self.tag_prefix,
+            &self.sections,
+        )?)
+    }
+
+    /// The next version from local tags and commits only
+    pub fn next_version(&self) -> Result<NextVersion, EaserError> {
+        Ok(next_version(
+            &self.repo_path,
+            self.previous_tag.as_ref(),
+            &self.target,
+            self.version.as_deref(),
+            &self.tag_prefix,
+            &self.sections,
+        )?)
+    }
+
+    /// Number of commits since the previous tag and those that do not follow
+    /// Conventional Commits
+    pub fn lint(&self) -> Result<(usize, Vec<Commit>), EaserError> {
+        Ok(lint_commits(
+            &self.repo_path,
+            self.previous_tag.as_ref(),
+            &self.target,
+            &self.tag_prefix,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn commit(repo: &Repository, message: &str) -> git2::Oid {
+        let sig = git2::Signature::now("t", "t@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let parents: Vec<git2::Commit> = repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_is_conventional() {
+        assert!(is_conventional("feat: add upload command"));
+        assert!(is_conventional("fix(api)!: drop v4 endpoints"));
+        assert!(!is_conventional("Add upload command"));
+        assert!(!is_conventional("feat:missing space"));
+        assert!(!is_conventional("feat(): empty scope"));
+        assert!(!is_conventional("feat add: space in type"));
+    }
+
+    #[test]
+    fn test_collect_history_since_latest_tag() {
+        let dir = tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let first = commit(&repo, "feat: initial");
+        repo.tag_lightweight("v0.1.0", &repo.find_object(first, None).unwrap(), false)
+            .unwrap();
+        commit(&repo, "fix: crash on empty body");
+        let head = commit(&repo, "update readme");
+
+        let history = collect_history(&repo, None, &head.to_string(), "v").unwrap();
+        let (total, invalid) =
+            lint_commits(dir.path().to_str().unwrap(), None, "HEAD", "v").unwrap();
+
+        assert_eq!(history.base_tag, "v0.1.0");
+        assert_eq!(history.base_version, Version::new(0, 1, 0));
+        assert_eq!(history.commits.len(), 2);
+        assert_eq!(history.commits[0].summary, "fix: crash on empty body");
+        assert_eq!(total, 2);
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].summary, "update readme");
+    }
+
+    #[test]
+    fn test_next_version() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let first = commit(&repo, "feat: initial");
+        repo.tag_lightweight("v1.2.3", &repo.find_object(first, None).unwrap(), false)
+            .unwrap();
+        commit(&repo, "docs: typo");
+
+        let nothing = next_version(path, None, "HEAD", None, "v", &default_sections()).unwrap();
+        commit(&repo, "feat(api): paging");
+        commit(&repo, "fix: off by one");
+        let minor = next_version(path, None, "HEAD", None, "v", &default_sections()).unwrap();
+
+        assert_eq!(nothing.bump, Bump::None);
+        assert_eq!(nothing.version, "1.2.3");
+        assert_eq!(minor.bump, Bump::Minor);
+        assert_eq!(minor.version, "1.3.0");
+        assert_eq!(minor.tag, "v1.3.0");
+        assert_eq!(minor.base_tag.as_deref(), Some("v1.2.3"));
+        assert_eq!(minor.commits, 3);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::backend::ApiError;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::error::Error;
+
+/// Category of a failure, reported in `--output json` and mapped to the
+/// process exit code
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Other,
+    Git,
+    Config,
+    InvalidVersion,
+    Auth,
+    TagConflict,
+    NotFound,
+    RateLimited,
+    Network,
+    PartialUpload,
+    Lint,
+}
+
+impl ErrorKind {
+    /// Exit code of the `easer` binary. 2 is left to usage errors and 3 to
+    /// `easer next-version` finding nothing to release
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Git => 4,
+            ErrorKind::Config => 5,
+            ErrorKind::InvalidVersion => 6,
+            ErrorKind::Auth => 7,
+            ErrorKind::TagConflict => 8,
+            ErrorKind::NotFound => 9,
+            ErrorKind::RateLimited => 10,
+            ErrorKind::Network => 11,
+            ErrorKind::PartialUpload => 12,
+            ErrorKind::Lint => 13,
+        }
+    }
+
+    /// Classify an error raised further down: an [`EaserError`], a forge's
+    /// [`ApiError`], or a git, HTTP, version or TOML error
+    pub fn of(e: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = e.downcast_ref::<EaserError>() {
+            return e.kind();
+        }
+        if let Some(e) = e.downcast_ref::<ApiError>() {
+            return Self::of_response(e.status, &e.body);
+        }
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return match e.status() {
+                Some(status) => Self::of_response(status, ""),
+                None => ErrorKind::Network,
+            };
+        }
+        if let Some(e) = e.downcast_ref::<git2::Error>() {
+            return match (e.code(), e.class()) {
+                (git2::ErrorCode::Auth, _) => ErrorKind::Auth,
+                (_, git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssl) => {
+                    ErrorKind::Network
+                }
+                _ => ErrorKind::Git,
+            };
+        }
+        if e.is::<semver::Error>() {
+            return ErrorKind::InvalidVersion;
+        }
+        if e.is::<toml::de::Error>() {
+            return ErrorKind::Config;
+        }
+        ErrorKind::Other
+    }
+
+    /// Forges answer an existing tag or release with 409 or 422, and GitHub
+    /// signals rate limiting with a 403
+    pub fn of_response(status: StatusCode, body: &str) -> Self {
+        let body = body.to_lowercase();
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => ErrorKind::RateLimited,
+            StatusCode::FORBIDDEN if body.contains("rate limit") => ErrorKind::RateLimited,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ErrorKind::Auth,
+            StatusCode::NOT_FOUND => ErrorKind::NotFound,
+            StatusCode::CONFLICT | StatusCode::UNPROCESSABLE_ENTITY
+                if body.contains("already") || body.contains("exist") =>
+            {
+                ErrorKind::TagConflict
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// The kind shared by all of `kinds`, otherwise [`ErrorKind::Other`]
+    pub fn common(kinds: impl IntoIterator<Item = ErrorKind>) -> Self {
+        let mut kinds = kinds.into_iter();
+        let first = kinds.next().unwrap_or(ErrorKind::Other);
+        if kinds.all(|kind| kind == first) {
+            first
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+/// Every failure easer reports, with the message shown to the user
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum EaserError {
+    /// The repository, a tag or the remote cannot be read
+    #[error("{0}")]
+    Git(String),
+    /// A config, credentials or token file is missing or invalid
+    #[error("{0}")]
+    Config(String),
+    /// The tag or version is not semantic versioning
+    #[error("{0}")]
+    InvalidVersion(String),
+    /// No token, or the forge rejected it
+    #[error("{0}")]
+    Auth(String),
+    /// The tag or its release already exists
+    #[error("{0}")]
+    TagConflict(String),
+    /// The repository, target or release does not exist
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    RateLimited(String),
+    /// The forge or git remote could not be reached
+    #[error("{0}")]
+    Network(String),
+    /// The release was created but some artifacts failed to upload
+    #[error("{0}")]
+    PartialUpload(String),
+    /// `easer lint` found commits that are not Conventional Commits
+    #[error("{0}")]
+    Lint(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl EaserError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match kind {
+            ErrorKind::Git => EaserError::Git(message),
+            ErrorKind::Config => EaserError::Config(message),
+            ErrorKind::InvalidVersion => EaserError::InvalidVersion(message),
+            ErrorKind::Auth => EaserError::Auth(message),
+            ErrorKind::TagConflict => EaserError::TagConflict(message),
+            ErrorKind::NotFound => EaserError::NotFound(message),
+            ErrorKind::RateLimited => EaserError::RateLimited(message),
+            ErrorKind::Network => EaserError::Network(message),
+            ErrorKind::PartialUpload => EaserError::PartialUpload(message),
+            ErrorKind::Lint => EaserError::Lint(message),
+            ErrorKind::Other => EaserError::Other(message),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            EaserError::Git(_) => ErrorKind::Git,
+            EaserError::Config(_) => ErrorKind::Config,
+            EaserError::InvalidVersion(_) => ErrorKind::InvalidVersion,
+            EaserError::Auth(_) => ErrorKind::Auth,
+            EaserError::TagConflict(_) => ErrorKind::TagConflict,
+            EaserError::NotFound(_) => ErrorKind::NotFound,
+            EaserError::RateLimited(_) => ErrorKind::RateLimited,
+            EaserError::Network(_) => ErrorKind::Network,
+            EaserError::PartialUpload(_) => ErrorKind::PartialUpload,
+            EaserError::Lint(_) => ErrorKind::Lint,
+            EaserError::Other(_) => ErrorKind::Other,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for EaserError {
+    fn from(e: Box<dyn Error + Send + Sync>) -> Self {
+        match e.downcast::<EaserError>() {
+            Ok(e) => *e,
+            Err(e) => EaserError::new(ErrorKind::of(e.as_ref()), e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_errors() {
+        let api_error = |status, body: &str| -> Box<dyn Error + Send + Sync> {
+            Box::new(ApiError {
+                status,
+                body: body.to_string(),
+            })
+        };
+        let kind = |e: Box<dyn Error + Send + Sync>| EaserError::from(e).kind();
+
+        assert_eq!(
+            kind(api_error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                r#"{"message": "Release already exists"}"#
+            )),
+            ErrorKind::TagConflict
+        );
+        assert_eq!(
+            kind(api_error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Validation Failed"
+            )),
+            ErrorKind::Other
+        );
+        assert_eq!(
+            kind(api_error(StatusCode::FORBIDDEN, "API rate limit exceeded")),
+            ErrorKind::RateLimited
+        );
+        assert_eq!(
+            kind(api_error(StatusCode::UNAUTHORIZED, "")),
+            ErrorKind::Auth
+        );
+        assert_eq!(
+            kind(api_error(StatusCode::NOT_FOUND, "")),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            kind(Box::new(semver::Version::parse("x").unwrap_err())),
+            ErrorKind::InvalidVersion
+        );
+        assert_eq!(
+            kind(Box::new(git2::Error::from_str("bad object"))),
+            ErrorKind::Git
+        );
+        assert_eq!(kind("plain".into()), ErrorKind::Other);
+
+        let typed: Box<dyn Error + Send + Sync> =
+            Box::new(EaserError::PartialUpload("1 failed".to_string()));
+        let e = EaserError::from(typed);
+        assert_eq!(e.exit_code(), 12);
+        assert_eq!(e.to_string(), "1 failed");
+    }
+
+    #[test]
+    fn test_common_kind() {
+        assert_eq!(
+            ErrorKind::common([ErrorKind::TagConflict, ErrorKind::TagConflict]),
+            ErrorKind::TagConflict
+        );
+        assert_eq!(
+            ErrorKind::common([ErrorKind::TagConflict, ErrorKind::Auth]),
+            ErrorKind::Other
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use crate::artifact::{self, LoadedArtifact};
+use crate::backend::{
+    api_request, download_file, encode_segment, expect_success, fetch_all_pages, normalize_api_url,
+    push_permission, read_json, read_uploaded_asset, run_preflight_checks, BackendConfig,
+    CheckOutcome, Forge, PreflightEndpoints, Release, ReleaseBackend, RemoteAsset, RemoteRelease,
+};
+use async_trait::async_trait;
+use reqwest::{multipart, Method, StatusCode};
+use std::error::Error;
+use tracing::info;
+
+/// Public Gitea instance, used when no `--api-url` is given
+pub const DEFAULT_API_URL: &str = "https://gitea.com";
+
+/// Path the API is served under, which `--api-url` may include
+pub const API_PATH: &str = "/api/v1";
+
+/// Gitea's default `MAX_RESPONSE_ITEMS`
+const PER_PAGE: usize = 50;
+
+/// Gitea API v1, also served unchanged by Forgejo (e.g. Codeberg)
+pub struct GiteaBackend {
+    config: BackendConfig,
+    base_url: String,
+}
+
+impl GiteaBackend {
+    pub fn new(config: BackendConfig) -> Self {
+        let base_url = normalize_api_url(&config.api_url, API_PATH);
+        GiteaBackend { config, base_url }
+    }
+
+    fn repo_url(&self) -> String {
+        format!(
+            "{}{}/repos/{}/{}",
+            self.base_url, API_PATH, self.config.owner, self.config.repo
+        )
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        api_request(
+            &self.config,
+            method,
+            url,
+            &self.authorization(self.config.token.expose()),
+            "application/json",
+        )
+    }
+}
+
+#[async_trait]
+impl ReleaseBackend for GiteaBackend {
+    fn forge(&self) -> Forge {
+        Forge::Gitea
+    }
+
+    fn repo_web_url(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.base_url, self.config.owner, self.config.repo
+        )
+    }
+
+    fn releases_url(&self) -> String {
+        format!("{}/releases", self.repo_url())
+    }
+
+    fn assets_url(&self, release_id: &str) -> String {
+        format!("{}/{}/assets", self.releases_url(), release_id)
+    }
+
+    fn authorization(&self, token: &str) -> String {
+        format!("token {}", token)
+    }
+
+    async fn create_release(
+        &self,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::POST, &self.releases_url())
+            .json(release)
+            .send()
+            .await?;
+        read_json(res, &self.config.token).await
+    }
+
+    async fn list_releases(&self) -> Result<Vec<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let url = self.releases_url();
+        fetch_all_pages(
+            |page| {
+                self.request(Method::GET, &url)
+                    .query(&[("page", page.to_string()), ("limit", PER_PAGE.to_string())])
+            },
+            PER_PAGE,
+            &self.config.token,
+        )
+        .await
+    }
+
+    async fn find_release_by_tag(
+        &self,
+        tag: &str,
+    ) -> Result<Option<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::GET,
+                &format!("{}/tags/{}", self.releases_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        read_json(res, &self.config.token).await
+    }
+
+    async fn update_release(
+        &self,
+        existing: &RemoteRelease,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::PATCH,
+                &format!("{}/{}", self.releases_url(), existing.numeric_id()?),
+            )
+            .json(release)
+            .send()
+            .await?;
+        read_json(res, &self.config.token).await
+    }
+
+    async fn upload_asset(
+        &self,
+        release: &RemoteRelease,
+        artifact: &LoadedArtifact,
+    ) -> Result<RemoteAsset, Box<dyn Error + Send + Sync>> {
+        let upload_url = self.assets_url(&release.numeric_id()?.to_string());
+        info!("Uploading to: {}", upload_url);
+
+        let file_part = multipart::Part::bytes(artifact.content.clone())
+            .file_name(artifact.filename.clone())
+            .mime_str(&artifact::content_type(
+                &artifact.filename,
+                &artifact.content,
+            ))?;
+        let form = multipart::Form::new().part("attachment", file_part);
+
+        // The asset name comes from the query, not from the part's file name
+        let res = self
+            .request(Method::POST, &upload_url)
+            .query(&[("name", artifact.filename.as_str())])
+            .timeout(self.config.upload_timeout)
+            .multipart(form)
+            .send()
+            .await?;
+        read_uploaded_asset(res, &self.config.token, &artifact.filename).await
+    }
+
+    async fn download_asset(
+        &self,
+        asset: &RemoteAsset,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        download_file(
+            &self.config,
+            &self.authorization(self.config.token.expose()),
+            &asset.browser_download_url,
+        )
+        .await
+    }
+
+    async fn delete_release(
+        &self,
+        release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::DELETE,
+                &format!("{}/{}", self.releases_url(), release.numeric_id()?),
+            )
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
+
+    async fn tag_exists(&self, tag: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::GET,
+                &format!("{}/tags/{}", self.repo_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        expect_success(res, &self.config.token).await?;
+        Ok(true)
+    }
+
+    async fn delete_tag(&self, tag: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::DELETE,
+                &format!("{}/tags/{}", self.repo_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
+
+    async fn preflight_checks(&self, tag_name: &str, target_commitish: &str) -> Vec<CheckOutcome> {
+        // `git/commits/{sha}` accepts branch and tag names as well as SHAs
+        let endpoints = PreflightEndpoints {
+            user_url: Some(format!("{}{}/user", self.base_url, API_PATH)),
+            repo_url: self.repo_url(),
+            repo_name: format!("{}/{}", self.config.owner, self.config.repo),
+            commit_url: format!(
+                "{}/git/commits/{}",
+                self.repo_url(),
+                encode_segment(target_commitish)
+            ),
+            push: push_permission,
+        };
+        let request = |url: &str| self.request(Method::GET, url);
+        run_preflight_checks(
+            self,
+            &request,
+            &self.config.token,
+            endpoints,
+            tag_name,
+            target_commitish,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::Token;
+    use mockito::{Matcher, Server};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn backend(url: &str) -> GiteaBackend {
+        GiteaBackend::new(BackendConfig {
+            api_url: format!("{}/api/v1", url),
+            owner: "infra".to_string(),
+            repo: "tool".to_string(),
+            token: Token::new("forgejo_token"),
+            client: reqwest::Client::new(),
+            upload_timeout: Duration::from_secs(5),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_prerelease_and_upload_asset() {
+        let mut server = Server::new_async().await;
+        let create = server
+            .mock("POST", "/api/v1/repos/infra/tool/releases")
+            .match_header("Authorization", "token forgejo_token")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"tag_name": "v2.0.0-rc.1", "draft": true, "prerelease": true}"#.to_string(),
+            ))
+            .with_status(201)
+            .with_body(r#"{"id": 9, "html_url": "https://git.example.com/infra/tool/releases/tag/v2.0.0-rc.1", "assets": []}"#)
+            .create_async()
+            .await;
+        let upload = server
+            .mock("POST", "/api/v1/repos/infra/tool/releases/9/assets")
+            .match_query(Matcher::UrlEncoded("name".into(), "tool.tar.gz".into()))
+            .match_header("Authorization", "token forgejo_token")
+            .match_body(Matcher::Regex(r#"name="attachment""#.to_string()))
+            .with_status(201)
+            .create_async()
+            .await;
+        let backend = backend(&server.url());
+
+        let release = backend
+            .create_release(&Release {
+                tag_name: "v2.0.0-rc.1".to_string(),
+                target_commitish: "main".to_string(),
+                name: "v2.0.0-rc.1".to_string(),
+                body: String::new(),
+                draft: true,
+                prerelease: true,
+            })
+            .await
+            .unwrap();
+        let artifact = LoadedArtifact {
+            filename: "tool.tar.gz".to_string(),
+            content: b"data".to_vec(),
+            path: PathBuf::from("tool.tar.gz"),
+        };
+        backend.upload_asset(&release, &artifact).await.unwrap();
+
+        assert_eq!(
+            backend.commit_url_prefix(),
+            format!("{}/infra/tool/commit", server.url())
+        );
+        create.assert_async().await;
+        upload.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_preflight_checks_pass() {
+        let mut server = Server::new_async().await;
+        let repo = "/api/v1/repos/infra/tool";
+        let mocks = vec![
+            server
+                .mock("GET", "/api/v1/user")
+                .with_status(200)
+                .with_body(r#"{"login": "ci"}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", repo)
+                .with_status(200)
+                .with_body(r#"{"permissions": {"admin": false, "push": true, "pull": true}}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", format!("{}/git/commits/main", repo).as_str())
+                .with_status(200)
+                .with_body(r#"{"sha": "abc"}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", format!("{}/tags/v1.0.0", repo).as_str())
+                .with_status(404)
+                .create_async()
+                .await,
+            server
+                .mock("GET", format!("{}/releases/tags/v1.0.0", repo).as_str())
+                .with_status(404)
+                .create_async()
+                .await,
+        ];
+
+        let checks = backend(&server.url())
+            .preflight_checks("v1.0.0", "main")
+            .await;
+
+        assert!(checks.iter().all(|c| c.outcome.is_ok()));
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+}
@@ -1,491 +1,478 @@
-use crate::args::Args;
-use crate::conventional::generate_release_info;
-use reqwest::{header, multipart};
-use semver::Version;
-use serde::{Deserialize, Serialize};
+use crate::artifact::{self, LoadedArtifact};
+use crate::backend::{
+    api_request, download_file, encode_segment, expect_success, fetch_all_pages, normalize_api_url,
+    push_permission, read_json, read_uploaded_asset, run_preflight_checks, BackendConfig,
+    CheckOutcome, Forge, PreflightEndpoints, Release, ReleaseBackend, RemoteAsset, RemoteRelease,
+};
+use crate::messages::Messages;
+use crate::token::Token;
+use async_trait::async_trait;
+use reqwest::{header, multipart, StatusCode};
+use serde::Deserialize;
 use std::error::Error;
-use std::path::Path;
-use tokio::fs;
-use tracing::{error, info, warn};
-
-#[derive(Serialize)]
-struct Release {
-    tag_name: String,
-    target_commitish: String,
-    name: String,
-    body: String,
-    draft: bool,
-    prerelease: bool,
-}
+use tracing::{error, info};
 
-#[derive(Deserialize)]
-struct GiteeReleaseResponse {
-    id: i64,
-    html_url: Option<String>,
-}
+/// Tags and releases are listed page by page; Gitee caps `per_page` at 100
+const PER_PAGE: usize = 100;
+
+/// Public Gitee API host, used when no `--api-url` is given
+pub const DEFAULT_API_URL: &str = "https://gitee.com";
 
-struct Messages<'a> {
-    invalid_tag: &'a str,
-    success: &'a str,
-    failure: &'a str,
-    api_error: &'a str,
-    upload_start: &'a str,
-    upload_success: &'a str,
-    upload_failure: &'a str,
-    file_read_error: &'a str,
+/// Environment variable older setups give the Gitee API URL in. It applies to
+/// Gitee only and ranks below every other source, see [`api_url_from_env`]
+pub const LEGACY_API_URL_ENV: &str = "GITEE_API_URL";
+
+/// The API URL from [`LEGACY_API_URL_ENV`], if set
+pub fn api_url_from_env() -> Option<String> {
+    std::env::var(LEGACY_API_URL_ENV)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
 }
 
-impl<'a> Messages<'a> {
-    fn new(lang: &str) -> Self {
-        match lang {
-            "en-us" => Messages {
-                invalid_tag: "Invalid semantic version tag name",
-                success: "Release created successfully",
-                failure: "Failed to create release",
-                api_error: "API request failed with status",
-                upload_start: "Uploading artifact",
-                upload_success: "Successfully uploaded artifact",
-                upload_failure: "Failed to upload artifact",
-                file_read_error: "Failed to read artifact file",
-            },
-            _ => Messages {
-                // Default to zh-cn
-                invalid_tag: "无效的语义化版本标签名称",
-                success: "版本发布成功创建",
-                failure: "创建版本发布失败",
-                api_error: "API 请求失败，状态码",
-                upload_start: "开始上传 artifact",
-                upload_success: "成功上传 artifact",
-                upload_failure: "上传 artifact 失败",
-                file_read_error: "读取 artifact 文件失败",
-            },
-        }
+/// Path the API is served under, which `--api-url` may include
+pub const API_PATH: &str = "/api/v5";
+
+/// Web host matching an API base URL, e.g. `https://api.gitee.example.com`
+/// serves pages from `https://gitee.example.com`
+pub fn web_url(api_base_url: &str) -> String {
+    let base = normalize_api_url(api_base_url, API_PATH);
+    match base.split_once("://") {
+        Some((scheme, host)) => match host.strip_prefix("api.") {
+            Some(web_host) => format!("{}://{}", scheme, web_host),
+            None => base,
+        },
+        None => base,
     }
 }
 
-pub async fn create_release(args: Args, api_base_url: Option<&str>) -> Result<(), Box<dyn Error>> {
-    let messages = Messages::new(&args.lang);
-
-    let tag_name: String;
-    let release_name: String;
-    let release_body: String;
-
-    if args.auto_gen_notes {
-        info!("Auto-generating release notes...");
-        let info = generate_release_info(
-            &args.repo_path,
-            args.previous_tag.as_ref(),
-            &args.target_commitish,
-            args.tag_name.as_deref(),
-        )?;
-        tag_name = info.tag_name;
-        release_name = info.name;
-        release_body = info.body;
-    } else {
-        tag_name = args.tag_name.clone().unwrap_or_default();
-        release_name = args.name.clone().unwrap_or_default();
-        release_body = args.body.clone().unwrap_or_default();
-        [&tag_name, &release_name, &release_body]
-            .iter()
-            .filter(|s| s.is_empty())
-            .for_each(|_| {
-                error!("Tag name, release name, and body cannot be empty");
-            });
-    }
+/// Check that `api_base_url` actually serves Gitee API v5 before doing any real work
+pub async fn probe_api(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    owner: &str,
+    repo: &str,
+    token: &Token,
+    lang: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let messages = Messages::new(lang);
+    let base_url = normalize_api_url(api_base_url, API_PATH);
+    let url = format!("{}{}/repos/{}/{}", base_url, API_PATH, owner, repo);
+    info!("Probing Gitee API: {}", url);
 
-    let tag_name_to_parse = tag_name.strip_prefix('v').unwrap_or(&tag_name);
-    if Version::parse(tag_name_to_parse).is_err() {
-        let err_msg = format!("{}: {}", messages.invalid_tag, tag_name);
+    let res = client
+        .get(&url)
+        .header(header::AUTHORIZATION, format!("token {}", token.expose()))
+        .header(header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("{} {}: {}", messages.api_unreachable, base_url, e))?;
+
+    let status = res.status();
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"));
+    // Anything speaking v5 answers with JSON, even for errors; HTML or an empty
+    // 404 means the path layout (and so the API version) does not match
+    if !is_json {
+        let err_msg = format!(
+            "{} {} (GET {} -> {})",
+            messages.api_mismatch, base_url, url, status
+        );
         error!("{}", err_msg);
         return Err(err_msg.into());
     }
+    Ok(())
+}
 
-    let release = Release {
-        tag_name: tag_name.clone(),
-        target_commitish: args.target_commitish.clone(),
-        name: release_name.clone(),
-        body: release_body.clone(),
-        draft: args.draft,
-        prerelease: args.prerelease,
-    };
+#[derive(Deserialize)]
+struct TagResponse {
+    name: String,
+}
 
-    let client = reqwest::Client::new();
-    let base_url = api_base_url.unwrap_or("https://gitee.com");
-    let url = format!(
-        "{}/api/v5/repos/{}/{}/releases",
-        base_url, args.owner, args.repo
-    );
+/// Gitee API v5, on gitee.com or a self-hosted instance
+pub struct GiteeBackend {
+    config: BackendConfig,
+    base_url: String,
+}
 
-    info!("Sending request to Gitee API: {}", url);
+impl GiteeBackend {
+    pub fn new(config: BackendConfig) -> Self {
+        let base_url = normalize_api_url(&config.api_url, API_PATH);
+        GiteeBackend { config, base_url }
+    }
 
-    let res = client
-        .post(&url)
-        .header(header::AUTHORIZATION, format!("token {}", args.token))
-        .header(header::CONTENT_TYPE, "application/json")
-        .header(header::ACCEPT, "application/json")
-        .json(&release)
-        .send()
-        .await?;
+    fn repo_url(&self) -> String {
+        format!(
+            "{}{}/repos/{}/{}",
+            self.base_url, API_PATH, self.config.owner, self.config.repo
+        )
+    }
 
-    let release_id: i64;
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        api_request(
+            &self.config,
+            method,
+            url,
+            &self.authorization(self.config.token.expose()),
+            "application/json",
+        )
+    }
+}
 
-    if res.status().is_success() {
-        let response_body = res.text().await?;
-        let release_response: GiteeReleaseResponse = match serde_json::from_str(&response_body) {
-            Ok(resp) => resp,
-            Err(e) => {
-                error!("Failed to parse release creation response: {}", e);
-                return Err(format!("Failed to parse release creation response: {}", e).into());
-            }
-        };
-        release_id = release_response.id;
-        if let Some(url) = release_response.html_url {
-            info!("{}: {}", messages.success, url);
-        } else {
-            info!("{}", messages.success);
-        }
+#[async_trait]
+impl ReleaseBackend for GiteeBackend {
+    fn forge(&self) -> Forge {
+        Forge::Gitee
+    }
 
-        if let Some(artifact_paths) = args.artifacts {
-            for artifact_path_str in artifact_paths {
-                let artifact_path = Path::new(&artifact_path_str);
-                if !artifact_path.is_file() {
-                    warn!(
-                        "Artifact path is not a file or does not exist, skipping: {}",
-                        artifact_path_str
-                    );
-                    continue;
-                }
-
-                let filename = match artifact_path.file_name() {
-                    Some(name) => name.to_string_lossy().to_string(),
-                    None => {
-                        warn!(
-                            "Could not get filename for artifact, skipping: {}",
-                            artifact_path_str
-                        );
-                        continue;
-                    }
-                };
-
-                info!("{}: {}", messages.upload_start, filename);
-
-                let file_content = match fs::read(&artifact_path).await {
-                    Ok(content) => content,
-                    Err(e) => {
-                        error!(
-                            "{}: {} - {}",
-                            messages.file_read_error, artifact_path_str, e
-                        );
-                        continue;
-                    }
-                };
-
-                let upload_url = format!(
-                    "{}/api/v5/repos/{}/{}/releases/{}/attach_files",
-                    base_url, args.owner, args.repo, release_id
-                );
-
-                let file_part = multipart::Part::bytes(file_content)
-                    .file_name(filename.clone())
-                    .mime_str("application/octet-stream")?;
-
-                let form = multipart::Form::new()
-                    .text("access_token", args.token.clone())
-                    .part("file", file_part);
-
-                info!("Uploading to: {}", upload_url);
-
-                let upload_res = client
-                    .post(&upload_url)
-                    .header(header::AUTHORIZATION, format!("token {}", args.token))
-                    .header(header::ACCEPT, "application/json")
-                    .multipart(form)
-                    .send()
-                    .await;
-
-                match upload_res {
-                    Ok(resp) => {
-                        if resp.status().is_success() {
-                            info!("{}: {}", messages.upload_success, filename);
-                        } else {
-                            let status = resp.status();
-                            let error_text = match resp.text().await {
-                                Ok(text) => text,
-                                Err(e) => {
-                                    error!("Failed to read upload error response body: {}", e);
-                                    String::from("Could not read error body")
-                                }
-                            };
-                            error!(
-                                status = status.as_str(),
-                                details = error_text.as_str(),
-                                filename = filename.as_str(),
-                                "{}: {} - Status: {}, Details: {}",
-                                messages.upload_failure,
-                                filename,
-                                status,
-                                error_text
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        error!(
-                            filename = filename.as_str(),
-                            error = e.to_string().as_str(),
-                            "{}: {}",
-                            messages.upload_failure,
-                            filename
-                        );
-                    }
-                }
-            }
-        }
-    } else {
-        let status = res.status();
-        let error_text = match res.text().await {
-            Ok(text) => text,
-            Err(e) => {
-                error!("Failed to read error response body: {}", e);
-                String::from("Could not read error body")
-            }
-        };
-        error!(
-            status = status.as_str(),
-            details = error_text.as_str(),
-            "{}: {}",
-            messages.failure,
-            status
-        );
-        return Err(format!("{}: {} - {}", messages.api_error, status, error_text).into());
+    fn repo_web_url(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            web_url(&self.base_url),
+            self.config.owner,
+            self.config.repo
+        )
     }
 
-    Ok(())
-}
+    fn releases_url(&self) -> String {
+        format!("{}/releases", self.repo_url())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::args::Args;
-    use mockito::Server;
-    use std::fs::File;
-    use std::io::Write;
-    use tempfile::tempdir;
+    fn assets_url(&self, release_id: &str) -> String {
+        format!("{}/{}/attach_files", self.releases_url(), release_id)
+    }
 
-    fn default_args() -> Args {
-        Args {
-            owner: "test_owner".to_string(),
-            repo: "test_repo".to_string(),
-            token: "test_token".to_string(),
-            repo_path: ".".to_string(),
-            previous_tag: None,
-            tag_name: Some("v1.0.0".to_string()),
-            name: Some("Test Release".to_string()),
-            body: Some("This is a test release.".to_string()),
-            target_commitish: "main".to_string(),
-            draft: false,
-            prerelease: false,
-            lang: "zh-cn".to_string(),
-            artifacts: None,
-            auto_gen_notes: false,
-        }
+    fn authorization(&self, token: &str) -> String {
+        format!("token {}", token)
     }
 
-    #[tokio::test]
-    async fn test_create_release_success() {
-        let mut server = Server::new_async().await;
-        let args = default_args();
-        let api_path = format!("/api/v5/repos/{}/{}/releases", args.owner, args.repo);
+    async fn create_release(
+        &self,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(reqwest::Method::POST, &self.releases_url())
+            .json(release)
+            .send()
+            .await?;
+        read_json(res, &self.config.token).await
+    }
 
-        let mock = server
-            .mock("POST", api_path.as_str())
-            .with_status(201)
-            .with_header("content-type", "application/json")
-            .with_body(r#"{"id": 1, "tag_name": "v1.0.0", "html_url": "http://example.com/release/v1.0.0"}"#)
-            .create_async()
-            .await;
+    async fn list_releases(&self) -> Result<Vec<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let url = self.releases_url();
+        fetch_all_pages(
+            |page| {
+                self.request(reqwest::Method::GET, &url).query(&[
+                    ("page", page.to_string()),
+                    ("per_page", PER_PAGE.to_string()),
+                    ("direction", "desc".to_string()),
+                ])
+            },
+            PER_PAGE,
+            &self.config.token,
+        )
+        .await
+    }
 
-        let result = create_release(args, Some(&server.url())).await;
+    async fn find_release_by_tag(
+        &self,
+        tag: &str,
+    ) -> Result<Option<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                reqwest::Method::GET,
+                &format!("{}/tags/{}", self.releases_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        // Gitee answers 200 with `null` when the tag has no release
+        read_json(res, &self.config.token).await
+    }
 
-        assert!(result.is_ok());
-        mock.assert_async().await;
+    async fn update_release(
+        &self,
+        existing: &RemoteRelease,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                reqwest::Method::PATCH,
+                &format!("{}/{}", self.releases_url(), existing.numeric_id()?),
+            )
+            .json(release)
+            .send()
+            .await?;
+        read_json(res, &self.config.token).await
     }
 
-    #[tokio::test]
-    async fn test_create_release_with_artifacts_success() {
-        let mut server = Server::new_async().await;
-        let dir = tempdir().unwrap();
-        let file_path1 = dir.path().join("artifact1.zip");
-        let file_path2 = dir.path().join("artifact2.txt");
-        File::create(&file_path1)
-            .unwrap()
-            .write_all(b"zip content")
-            .unwrap();
-        File::create(&file_path2)
-            .unwrap()
-            .write_all(b"text content")
-            .unwrap();
+    async fn upload_asset(
+        &self,
+        release: &RemoteRelease,
+        artifact: &LoadedArtifact,
+    ) -> Result<RemoteAsset, Box<dyn Error + Send + Sync>> {
+        let upload_url = self.assets_url(&release.numeric_id()?.to_string());
+        info!("Uploading to: {}", upload_url);
+
+        let file_part = multipart::Part::bytes(artifact.content.clone())
+            .file_name(artifact.filename.clone())
+            .mime_str(&artifact::content_type(
+                &artifact.filename,
+                &artifact.content,
+            ))?;
+        let form = multipart::Form::new().part("file", file_part);
+
+        let res = self
+            .request(reqwest::Method::POST, &upload_url)
+            .timeout(self.config.upload_timeout)
+            .multipart(form)
+            .send()
+            .await?;
+        read_uploaded_asset(res, &self.config.token, &artifact.filename).await
+    }
 
-        let args = Args {
-            artifacts: Some(vec![
-                file_path1.to_str().unwrap().to_string(),
-                file_path2.to_str().unwrap().to_string(),
-            ]),
-            ..default_args()
-        };
-        let release_api_path = format!("/api/v5/repos/{}/{}/releases", args.owner, args.repo);
-        let release_id = 123;
+    async fn download_asset(
+        &self,
+        asset: &RemoteAsset,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        download_file(
+            &self.config,
+            &self.authorization(self.config.token.expose()),
+            &asset.browser_download_url,
+        )
+        .await
+    }
 
-        let tag = args.tag_name.clone().unwrap();
-        let release_mock = server
-            .mock("POST", release_api_path.as_str())
-            .with_status(201)
-            .with_header("content-type", "application/json")
-            .with_body(format!(
-                r#"{{"id": {}, "tag_name": "{}", "html_url": "http://example.com/release/{}"}}"#,
-                release_id, tag, tag
-            ))
-            .create_async()
-            .await;
+    async fn delete_release(
+        &self,
+        release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                reqwest::Method::DELETE,
+                &format!("{}/{}", self.releases_url(), release.numeric_id()?),
+            )
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
 
-        let upload_path = format!(
-            "/api/v5/repos/{}/{}/releases/{}/attach_files",
-            args.owner, args.repo, release_id
-        );
+    async fn tag_exists(&self, tag: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let url = format!("{}/tags", self.repo_url());
+        let tags: Vec<TagResponse> = fetch_all_pages(
+            |page| {
+                self.request(reqwest::Method::GET, &url).query(&[
+                    ("page", page.to_string()),
+                    ("per_page", PER_PAGE.to_string()),
+                ])
+            },
+            PER_PAGE,
+            &self.config.token,
+        )
+        .await?;
+        Ok(tags.iter().any(|t| t.name == tag))
+    }
 
-        let upload_mock1 = server
-            .mock("POST", upload_path.as_str())
-            .match_header("Authorization", format!("token {}", args.token).as_str())
-            .match_header("Accept", "application/json")
-            .with_status(200)
-            .with_header("content-type", "application/json")
-            .with_body(r#"{\"name\": \"artifact1.zip\", \"url\": \"...\"}"#)
-            .create_async()
-            .await;
+    async fn delete_tag(&self, tag: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                reqwest::Method::DELETE,
+                &format!("{}/tags/{}", self.repo_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
 
-        let upload_mock2 = server
-            .mock("POST", upload_path.as_str())
-            .match_header("Authorization", format!("token {}", args.token).as_str())
-            .match_header("Accept", "application/json")
-            .with_status(200)
-            .with_header("content-type", "application/json")
-            .with_body(r#"{\"name\": \"artifact2.txt\", \"url\": \"...\"}"#)
-            .create_async()
-            .await;
+    async fn preflight_checks(&self, tag_name: &str, target_commitish: &str) -> Vec<CheckOutcome> {
+        let endpoints = PreflightEndpoints {
+            user_url: Some(format!("{}{}/user", self.base_url, API_PATH)),
+            repo_url: self.repo_url(),
+            repo_name: format!("{}/{}", self.config.owner, self.config.repo),
+            commit_url: format!(
+                "{}/commits/{}",
+                self.repo_url(),
+                encode_segment(target_commitish)
+            ),
+            push: push_permission,
+        };
+        let request = |url: &str| self.request(reqwest::Method::GET, url);
+        run_preflight_checks(
+            self,
+            &request,
+            &self.config.token,
+            endpoints,
+            tag_name,
+            target_commitish,
+        )
+        .await
+    }
+}
 
-        let result = create_release(args, Some(&server.url())).await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use std::path::PathBuf;
+    use std::time::Duration;
 
-        assert!(result.is_ok(), "create_release failed: {:?}", result.err());
-        release_mock.assert_async().await;
-        upload_mock1.assert_async().await;
-        upload_mock2.assert_async().await;
+    fn backend(url: &str) -> GiteeBackend {
+        GiteeBackend::new(BackendConfig {
+            api_url: format!("{}/api/v5/", url),
+            owner: "test_owner".to_string(),
+            repo: "test_repo".to_string(),
+            token: Token::new("test_token"),
+            client: reqwest::Client::new(),
+            upload_timeout: Duration::from_secs(5),
+        })
+    }
+
+    #[test]
+    fn test_api_and_web_url() {
+        assert_eq!(
+            normalize_api_url("https://gitee.example.com/api/v5/", API_PATH),
+            "https://gitee.example.com"
+        );
+        assert_eq!(
+            web_url("https://api.gitee.example.com"),
+            "https://gitee.example.com"
+        );
+        assert_eq!(web_url(DEFAULT_API_URL), "https://gitee.com");
     }
 
     #[tokio::test]
-    async fn test_create_release_success_no_html_url() {
+    async fn test_probe_api_ok() {
         let mut server = Server::new_async().await;
-        let args = default_args();
-        let api_path = format!("/api/v5/repos/{}/{}/releases", args.owner, args.repo);
-
         let mock = server
-            .mock("POST", api_path.as_str())
-            .with_status(201)
+            .mock("GET", "/api/v5/repos/test_owner/test_repo")
+            .with_status(404)
             .with_header("content-type", "application/json")
-            .with_body(r#"{"id": 1, "tag_name": "v1.0.0"}"#)
+            .with_body(r#"{"message": "Not Found Project"}"#)
             .create_async()
             .await;
 
-        let result = create_release(args, Some(&server.url())).await;
+        let client = reqwest::Client::new();
+        let result = probe_api(
+            &client,
+            &server.url(),
+            "test_owner",
+            "test_repo",
+            &Token::new("t"),
+            "en-us",
+        )
+        .await;
 
         assert!(result.is_ok());
         mock.assert_async().await;
     }
 
     #[tokio::test]
-    async fn test_create_release_api_error() {
+    async fn test_probe_api_version_mismatch() {
         let mut server = Server::new_async().await;
-        let args = default_args();
-        let api_path = format!("/api/v5/repos/{}/{}/releases", args.owner, args.repo);
-
         let mock = server
-            .mock("POST", api_path.as_str())
-            .with_status(401)
-            .with_header("content-type", "application/json")
-            .with_body(r#"{"message": "Unauthorized"}"#)
+            .mock("GET", "/api/v5/repos/test_owner/test_repo")
+            .with_status(404)
+            .with_header("content-type", "text/html")
+            .with_body("<html>404</html>")
             .create_async()
             .await;
 
-        let result = create_release(args, Some(&server.url())).await;
+        let client = reqwest::Client::new();
+        let result = probe_api(
+            &client,
+            &format!("{}/api/v5", server.url()),
+            "test_owner",
+            "test_repo",
+            &Token::new("t"),
+            "en-us",
+        )
+        .await;
 
-        assert!(result.is_err());
         assert!(result
             .unwrap_err()
             .to_string()
-            .contains("API 请求失败，状态码: 401 Unauthorized - {\"message\": \"Unauthorized\"}"));
+            .contains("Endpoint does not serve Gitee API v5"));
         mock.assert_async().await;
     }
 
     #[tokio::test]
-    async fn test_create_release_invalid_tag() {
-        let args = Args {
-            tag_name: Some("invalid-tag".to_string()),
-            ..default_args()
-        };
-
-        let result = create_release(args, None).await;
-
-        assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("无效的语义化版本标签名称: invalid-tag"));
-    }
-
-    #[tokio::test]
-    async fn test_create_release_success_en_us() {
+    async fn test_find_release_by_tag_null_is_absent() {
         let mut server = Server::new_async().await;
-        let args = Args {
-            lang: "en-us".to_string(),
-            ..default_args()
-        };
-        let api_path = format!("/api/v5/repos/{}/{}/releases", args.owner, args.repo);
-
-        let mock = server
-            .mock("POST", api_path.as_str())
-            .with_status(201)
-            .with_header("content-type", "application/json")
-            .with_body(r#"{"id": 1, "tag_name": "v1.0.0", "html_url": "http://example.com/release/v1.0.0"}"#)
+        let missing = server
+            .mock(
+                "GET",
+                "/api/v5/repos/test_owner/test_repo/releases/tags/v1.0.0",
+            )
+            .with_status(200)
+            .with_body("null")
             .create_async()
             .await;
+        let found = server
+            .mock(
+                "GET",
+                "/api/v5/repos/test_owner/test_repo/releases/tags/v0.9.0",
+            )
+            .with_status(200)
+            .with_body(r#"{"id": 7, "tag_name": "v0.9.0", "body": null, "assets": []}"#)
+            .create_async()
+            .await;
+        let backend = backend(&server.url());
 
-        let result = create_release(args, Some(&server.url())).await;
-
-        assert!(result.is_ok());
-        mock.assert_async().await;
+        assert!(backend
+            .find_release_by_tag("v1.0.0")
+            .await
+            .unwrap()
+            .is_none());
+        let release = backend
+            .find_release_by_tag("v0.9.0")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(release.id, Some(7));
+        missing.assert_async().await;
+        found.assert_async().await;
     }
 
     #[tokio::test]
-    async fn test_create_release_api_error_en_us() {
+    async fn test_upload_asset_and_delete() {
         let mut server = Server::new_async().await;
-        let args = Args {
-            lang: "en-us".to_string(),
-            ..default_args()
-        };
-        let api_path = format!("/api/v5/repos/{}/{}/releases", args.owner, args.repo);
-
-        let mock = server
-            .mock("POST", api_path.as_str())
-            .with_status(404)
-            .with_header("content-type", "application/json")
-            .with_body(r#"{"message": "Not Found"}"#)
+        let upload = server
+            .mock(
+                "POST",
+                "/api/v5/repos/test_owner/test_repo/releases/3/attach_files",
+            )
+            .match_header("Authorization", "token test_token")
+            .with_status(201)
+            .create_async()
+            .await;
+        let delete = server
+            .mock("DELETE", "/api/v5/repos/test_owner/test_repo/releases/3")
+            .with_status(204)
             .create_async()
             .await;
+        let backend = backend(&server.url());
+        let artifact = LoadedArtifact {
+            filename: "app.txt".to_string(),
+            content: b"hello".to_vec(),
+            path: PathBuf::from("app.txt"),
+        };
+        let release = RemoteRelease {
+            id: Some(3),
+            ..Default::default()
+        };
 
-        let result = create_release(args, Some(&server.url())).await;
+        backend.upload_asset(&release, &artifact).await.unwrap();
+        backend.delete_release(&release).await.unwrap();
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains(
-            "API request failed with status: 404 Not Found - {\"message\": \"Not Found\"}"
-        ));
-        mock.assert_async().await;
+        assert_eq!(
+            backend.commit_url_prefix(),
+            format!("{}/test_owner/test_repo/commit", server.url())
+        );
+        upload.assert_async().await;
+        delete.assert_async().await;
     }
 }
@@ -0,0 +1,548 @@
+use crate::artifact::{self, LoadedArtifact};
+use crate::backend::{
+    api_request, download_file, encode_segment, expect_success, fetch_all_pages, push_permission,
+    read_json, read_uploaded_asset, run_preflight_checks, BackendConfig, CheckOutcome, Forge,
+    PreflightEndpoints, Release, ReleaseBackend, RemoteAsset, RemoteRelease,
+};
+use async_trait::async_trait;
+use reqwest::{header, Method, StatusCode};
+use std::error::Error;
+use tracing::info;
+
+/// Public GitHub API host
+const PUBLIC_API_HOST: &str = "api.github.com";
+
+const API_VERSION: &str = "2022-11-28";
+
+/// Largest page size the REST API allows
+const PER_PAGE: usize = 100;
+
+/// Split `https://host/path` into scheme, host and path
+fn split_url(url: &str) -> (&str, &str, &str) {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+    match rest.find('/') {
+        Some(i) => (scheme, &rest[..i], &rest[i..]),
+        None => (scheme, rest, ""),
+    }
+}
+
+/// Web host for an API base URL: `api.github.com` serves `github.com`,
+/// GitHub Enterprise serves the API under `/api/v3` of the web host
+pub fn web_url(api_base_url: &str) -> String {
+    let base = api_base_url.trim_end_matches('/');
+    let (scheme, host, path) = split_url(base);
+    if host == PUBLIC_API_HOST {
+        return format!("{}://github.com", scheme);
+    }
+    format!(
+        "{}://{}{}",
+        scheme,
+        host,
+        path.strip_suffix("/api/v3").unwrap_or(path)
+    )
+}
+
+/// Asset host for an API base URL, used when a release carries no `upload_url`:
+/// `uploads.github.com`, or `/api/uploads` on GitHub Enterprise
+pub fn uploads_url(api_base_url: &str) -> String {
+    let base = api_base_url.trim_end_matches('/');
+    let (scheme, host, _) = split_url(base);
+    if host == PUBLIC_API_HOST {
+        return format!("{}://uploads.github.com", scheme);
+    }
+    format!("{}/api/uploads", web_url(base))
+}
+
+/// GitHub REST API, on github.com or GitHub Enterprise Server
+pub struct GithubBackend {
+    config: BackendConfig,
+    base_url: String,
+}
+
+impl GithubBackend {
+    pub fn new(config: BackendConfig) -> Self {
+        let base_url = config.api_url.trim_end_matches('/').to_string();
+        GithubBackend { config, base_url }
+    }
+
+    fn repo_url(&self) -> String {
+        format!(
+            "{}/repos/{}/{}",
+            self.base_url, self.config.owner, self.config.repo
+        )
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        api_request(
+            &self.config,
+            method,
+            url,
+            &self.authorization(self.config.token.expose()),
+            "application/vnd.github+json",
+        )
+        .header("X-GitHub-Api-Version", API_VERSION)
+    }
+}
+
+#[async_trait]
+impl ReleaseBackend for GithubBackend {
+    fn forge(&self) -> Forge {
+        Forge::Github
+    }
+
+    fn repo_web_url(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            web_url(&self.base_url),
+            self.config.owner,
+            self.config.repo
+        )
+    }
+
+    fn releases_url(&self) -> String {
+        format!("{}/releases", self.repo_url())
+    }
+
+    fn assets_url(&self, release_id: &str) -> String {
+        format!(
+            "{}/repos/{}/{}/releases/{}/assets",
+            uploads_url(&self.base_url),
+            self.config.owner,
+            self.config.repo,
+            release_id
+        )
+    }
+
+    fn authorization(&self, token: &str) -> String {
+        format!("Bearer {}", token)
+    }
+
+    async fn create_release(
+        &self,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::POST, &self.releases_url())
+            .json(release)
+            .send()
+            .await?;
+        read_json(res, &self.config.token).await
+    }
+
+    async fn list_releases(&self) -> Result<Vec<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let url = self.releases_url();
+        fetch_all_pages(
+            |page| {
+                self.request(Method::GET, &url).query(&[
+                    ("page", page.to_string()),
+                    ("per_page", PER_PAGE.to_string()),
+                ])
+            },
+            PER_PAGE,
+            &self.config.token,
+        )
+        .await
+    }
+
+    /// `releases/tags/{tag}` only knows published releases, so a miss is
+    /// looked up again in the listing, which includes drafts
+    async fn find_release_by_tag(
+        &self,
+        tag: &str,
+    ) -> Result<Option<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::GET,
+                &format!("{}/tags/{}", self.releases_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        if res.status() != StatusCode::NOT_FOUND {
+            return read_json(res, &self.config.token).await;
+        }
+        let releases = self.list_releases().await?;
+        Ok(releases.into_iter().find(|release| release.tag_name == tag))
+    }
+
+    async fn update_release(
+        &self,
+        existing: &RemoteRelease,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::PATCH,
+                &format!("{}/{}", self.releases_url(), existing.numeric_id()?),
+            )
+            .json(release)
+            .send()
+            .await?;
+        read_json(res, &self.config.token).await
+    }
+
+    async fn upload_asset(
+        &self,
+        release: &RemoteRelease,
+        artifact: &LoadedArtifact,
+    ) -> Result<RemoteAsset, Box<dyn Error + Send + Sync>> {
+        // `upload_url` is a URI template ending in `{?name,label}`
+        let upload_url = match &release.upload_url {
+            Some(url) => url.split('{').next().unwrap_or(url).to_string(),
+            None => self.assets_url(&release.numeric_id()?.to_string()),
+        };
+        info!("Uploading to: {}", upload_url);
+
+        let res = self
+            .request(Method::POST, &upload_url)
+            .query(&[("name", artifact.filename.as_str())])
+            .header(
+                header::CONTENT_TYPE,
+                artifact::content_type(&artifact.filename, &artifact.content),
+            )
+            .timeout(self.config.upload_timeout)
+            .body(artifact.content.clone())
+            .send()
+            .await?;
+        read_uploaded_asset(res, &self.config.token, &artifact.filename).await
+    }
+
+    async fn download_asset(
+        &self,
+        asset: &RemoteAsset,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let url = asset
+            .api_url
+            .as_deref()
+            .unwrap_or(&asset.browser_download_url);
+        download_file(
+            &self.config,
+            &self.authorization(self.config.token.expose()),
+            url,
+        )
+        .await
+    }
+
+    async fn delete_release(
+        &self,
+        release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::DELETE,
+                &format!("{}/{}", self.releases_url(), release.numeric_id()?),
+            )
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
+
+    async fn tag_exists(&self, tag: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::GET,
+                &format!("{}/git/ref/tags/{}", self.repo_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        expect_success(res, &self.config.token).await?;
+        Ok(true)
+    }
+
+    async fn delete_tag(&self, tag: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(
+                Method::DELETE,
+                &format!("{}/git/refs/tags/{}", self.repo_url(), encode_segment(tag)),
+            )
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
+
+    async fn preflight_checks(&self, tag_name: &str, target_commitish: &str) -> Vec<CheckOutcome> {
+        // The Actions `GITHUB_TOKEN` cannot read `/user`, so the token and
+        // push checks both come from the repository, which any token can read
+        let endpoints = PreflightEndpoints {
+            user_url: None,
+            repo_url: self.repo_url(),
+            repo_name: format!("{}/{}", self.config.owner, self.config.repo),
+            commit_url: format!(
+                "{}/commits/{}",
+                self.repo_url(),
+                encode_segment(target_commitish)
+            ),
+            push: push_permission,
+        };
+        let request = |url: &str| self.request(Method::GET, url);
+        run_preflight_checks(
+            self,
+            &request,
+            &self.config.token,
+            endpoints,
+            tag_name,
+            target_commitish,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::CheckKind;
+    use crate::token::Token;
+    use mockito::{Matcher, Server};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn backend(url: &str) -> GithubBackend {
+        GithubBackend::new(BackendConfig {
+            api_url: url.to_string(),
+            owner: "octo".to_string(),
+            repo: "app".to_string(),
+            token: Token::new("gh_token"),
+            client: reqwest::Client::new(),
+            upload_timeout: Duration::from_secs(5),
+        })
+    }
+
+    #[test]
+    fn test_web_and_uploads_url() {
+        assert_eq!(web_url("https://api.github.com/"), "https://github.com");
+        assert_eq!(
+            uploads_url("https://api.github.com"),
+            "https://uploads.github.com"
+        );
+        assert_eq!(
+            web_url("https://ghe.example.com/api/v3"),
+            "https://ghe.example.com"
+        );
+        assert_eq!(
+            uploads_url("https://ghe.example.com/api/v3"),
+            "https://ghe.example.com/api/uploads"
+        );
+        assert_eq!(
+            backend("https://api.github.com").commit_url_prefix(),
+            "https://github.com/octo/app/commit"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_release_and_upload_asset() {
+        let mut server = Server::new_async().await;
+        let create = server
+            .mock("POST", "/repos/octo/app/releases")
+            .match_header("Authorization", "Bearer gh_token")
+            .match_header("X-GitHub-Api-Version", API_VERSION)
+            .match_body(Matcher::PartialJsonString(
+                r#"{"tag_name": "v1.0.0", "draft": true}"#.to_string(),
+            ))
+            .with_status(201)
+            .with_body(format!(
+                r#"{{"id": 42, "html_url": "https://github.com/octo/app/releases/v1.0.0", "upload_url": "{}/uploads/repos/octo/app/releases/42/assets{{?name,label}}"}}"#,
+                server.url()
+            ))
+            .create_async()
+            .await;
+        let upload = server
+            .mock("POST", "/uploads/repos/octo/app/releases/42/assets")
+            .match_query(Matcher::UrlEncoded("name".into(), "app.txt".into()))
+            .match_header("content-type", "text/plain")
+            .match_body("hello")
+            .with_status(201)
+            .create_async()
+            .await;
+        let backend = backend(&server.url());
+
+        let release = backend
+            .create_release(&Release {
+                tag_name: "v1.0.0".to_string(),
+                target_commitish: "main".to_string(),
+                name: "v1.0.0".to_string(),
+                body: String::new(),
+                draft: true,
+                prerelease: false,
+            })
+            .await
+            .unwrap();
+        let artifact = LoadedArtifact {
+            filename: "app.txt".to_string(),
+            content: b"hello".to_vec(),
+            path: PathBuf::from("app.txt"),
+        };
+        backend.upload_asset(&release, &artifact).await.unwrap();
+
+        assert_eq!(release.id, Some(42));
+        create.assert_async().await;
+        upload.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_preflight_existing_tag() {
+        let mut server = Server::new_async().await;
+        let _repo = server
+            .mock("GET", "/repos/octo/app")
+            .with_status(200)
+            .with_body(r#"{"permissions": {"push": false}}"#)
+            .create_async()
+            .await;
+        let _commit = server
+            .mock("GET", "/repos/octo/app/commits/main")
+            .with_status(200)
+            .with_body(r#"{"sha": "abc"}"#)
+            .create_async()
+            .await;
+        let _tag = server
+            .mock("GET", "/repos/octo/app/git/ref/tags/v1.0.0")
+            .with_status(200)
+            .with_body(r#"{"ref": "refs/tags/v1.0.0"}"#)
+            .create_async()
+            .await;
+        let _release = server
+            .mock("GET", "/repos/octo/app/releases/tags/v1.0.0")
+            .with_status(404)
+            .with_body(r#"{"message": "Not Found"}"#)
+            .create_async()
+            .await;
+        let _releases = server
+            .mock("GET", "/repos/octo/app/releases")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body("[]")
+            .create_async()
+            .await;
+
+        let checks = backend(&server.url())
+            .preflight_checks("v1.0.0", "main")
+            .await;
+        let failed: Vec<CheckKind> = checks
+            .iter()
+            .filter(|c| c.outcome.is_err())
+            .map(|c| c.kind)
+            .collect();
+
+        assert_eq!(failed, [CheckKind::PushPermission, CheckKind::TagAbsent]);
+    }
+
+    #[tokio::test]
+    async fn test_preflight_actions_token_and_draft_release() {
+        let mut server = Server::new_async().await;
+        // The Actions token is refused here, pre-flight must not ask
+        let user = server
+            .mock("GET", "/user")
+            .with_status(403)
+            .expect(0)
+            .create_async()
+            .await;
+        let _repo = server
+            .mock("GET", "/repos/octo/app")
+            .with_status(200)
+            .with_body(r#"{"permissions": {"push": true}}"#)
+            .create_async()
+            .await;
+        let _commit = server
+            .mock("GET", "/repos/octo/app/commits/main")
+            .with_status(200)
+            .with_body(r#"{"sha": "abc"}"#)
+            .create_async()
+            .await;
+        let _tag = server
+            .mock("GET", "/repos/octo/app/git/ref/tags/v1.0.0")
+            .with_status(404)
+            .create_async()
+            .await;
+        let _release = server
+            .mock("GET", "/repos/octo/app/releases/tags/v1.0.0")
+            .with_status(404)
+            .create_async()
+            .await;
+        let _releases = server
+            .mock("GET", "/repos/octo/app/releases")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(r#"[{"id": 5, "tag_name": "v1.0.0", "draft": true}]"#)
+            .create_async()
+            .await;
+
+        let checks = backend(&server.url())
+            .preflight_checks("v1.0.0", "main")
+            .await;
+        let failed: Vec<CheckKind> = checks
+            .iter()
+            .filter(|c| c.outcome.is_err())
+            .map(|c| c.kind)
+            .collect();
+
+        assert_eq!(failed, [CheckKind::ReleaseAbsent]);
+        user.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_download_asset_from_api() {
+        let mut server = Server::new_async().await;
+        let download = server
+            .mock("GET", "/repos/octo/app/releases/assets/5")
+            .match_header("Authorization", "Bearer gh_token")
+            .match_header("Accept", "application/octet-stream")
+            .with_status(200)
+            .with_body("archive")
+            .create_async()
+            .await;
+        let backend = backend(&server.url());
+        let asset = RemoteAsset {
+            name: "tool.tar.gz".to_string(),
+            browser_download_url: "https://github.com/octo/app/releases/download/v1/tool.tar.gz"
+                .to_string(),
+            api_url: Some(format!("{}/repos/octo/app/releases/assets/5", server.url())),
+        };
+
+        let content = backend.download_asset(&asset).await.unwrap();
+
+        assert_eq!(content, b"archive");
+        download.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_ref_names_with_slash_are_encoded() {
+        let mut server = Server::new_async().await;
+        let commit = server
+            .mock("GET", "/repos/octo/app/commits/feature%2Flogin")
+            .with_status(200)
+            .with_body(r#"{"sha": "abc"}"#)
+            .create_async()
+            .await;
+        let tag = server
+            .mock("GET", "/repos/octo/app/git/ref/tags/release%2F1.0")
+            .with_status(404)
+            .create_async()
+            .await;
+        let release = server
+            .mock("GET", "/repos/octo/app/releases/tags/release%2F1.0")
+            .with_status(200)
+            .with_body(r#"{"id": 3, "tag_name": "release/1.0"}"#)
+            .create_async()
+            .await;
+
+        let checks = backend(&server.url())
+            .preflight_checks("release/1.0", "feature/login")
+            .await;
+        let outcome = |kind| {
+            checks
+                .iter()
+                .find(|c| c.kind == kind)
+                .unwrap()
+                .outcome
+                .clone()
+        };
+
+        assert_eq!(outcome(CheckKind::Target), Ok("feature/login".to_string()));
+        assert_eq!(outcome(CheckKind::TagAbsent), Ok("release/1.0".to_string()));
+        assert!(outcome(CheckKind::ReleaseAbsent).is_err());
+        commit.assert_async().await;
+        tag.assert_async().await;
+        release.assert_async().await;
+    }
+}
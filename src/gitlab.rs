@@ -0,0 +1,604 @@
+use crate::artifact::{self, LoadedArtifact};
+use crate::backend::{
+    api_request, download_file, encode_segment, expect_success, fetch_all_pages, normalize_api_url,
+    read_json, read_upload, run_preflight_checks, BackendConfig, CheckOutcome, Forge,
+    PreflightEndpoints, Release, ReleaseBackend, RemoteAsset, RemoteRelease,
+};
+use async_trait::async_trait;
+use reqwest::{header, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tracing::{info, warn};
+
+/// Public GitLab instance, used when no `--api-url` is given
+pub const DEFAULT_API_URL: &str = "https://gitlab.com";
+
+/// Path the API is served under, which `--api-url` may include
+pub const API_PATH: &str = "/api/v4";
+
+/// Largest page size the REST API allows
+const PER_PAGE: usize = 100;
+
+/// Access level needed to create releases and publish packages (Developer)
+const DEVELOPER_ACCESS: u32 = 30;
+
+/// Body of `POST /projects/:id/releases`; GitLab has no draft or prerelease flag
+#[derive(Serialize)]
+struct GitlabRelease<'a> {
+    tag_name: &'a str,
+    #[serde(rename = "ref")]
+    git_ref: &'a str,
+    name: &'a str,
+    description: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(rename = "_links", default)]
+    links: Option<ReleaseLinks>,
+    #[serde(default)]
+    assets: Option<ReleaseAssets>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseAssets {
+    #[serde(default)]
+    links: Vec<AssetLinkResponse>,
+}
+
+#[derive(Deserialize)]
+struct AssetLinkResponse {
+    name: String,
+    url: String,
+    #[serde(default)]
+    direct_asset_url: Option<String>,
+}
+
+impl From<AssetLinkResponse> for RemoteAsset {
+    fn from(link: AssetLinkResponse) -> Self {
+        RemoteAsset {
+            browser_download_url: link.direct_asset_url.unwrap_or(link.url),
+            name: link.name,
+            api_url: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ReleaseLinks {
+    #[serde(rename = "self")]
+    self_url: Option<String>,
+}
+
+/// Generic package versions do not accept the `+` of semver build metadata,
+/// so `v1.0.0+build.5` is published as `v1.0.0_build.5`
+fn package_version(tag: &str) -> String {
+    tag.replace('+', "_")
+}
+
+impl From<ReleaseResponse> for RemoteRelease {
+    fn from(res: ReleaseResponse) -> Self {
+        // Only linked assets are listed; GitLab's generated source archives are not
+        let assets = res
+            .assets
+            .map(|assets| assets.links)
+            .unwrap_or_default()
+            .into_iter()
+            .map(RemoteAsset::from)
+            .collect();
+        RemoteRelease {
+            target_commitish: res.tag_name.clone(),
+            tag_name: res.tag_name,
+            name: res.name.unwrap_or_default(),
+            body: res.description.unwrap_or_default(),
+            html_url: res.links.and_then(|links| links.self_url),
+            assets,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AssetLink<'a> {
+    name: &'a str,
+    url: &'a str,
+    link_type: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ProjectResponse {
+    permissions: Option<ProjectPermissions>,
+}
+
+#[derive(Deserialize)]
+struct ProjectPermissions {
+    project_access: Option<Access>,
+    group_access: Option<Access>,
+}
+
+#[derive(Deserialize)]
+struct Access {
+    access_level: u32,
+}
+
+#[derive(Deserialize)]
+struct PackageResponse {
+    id: i64,
+}
+
+/// Push check from the project's access levels: creating releases and
+/// publishing packages needs Developer
+fn developer_access(project_body: &str) -> Result<String, String> {
+    let project: ProjectResponse = serde_json::from_str(project_body).map_err(|e| e.to_string())?;
+    let access_level = project.permissions.and_then(|permissions| {
+        [permissions.project_access, permissions.group_access]
+            .into_iter()
+            .flatten()
+            .map(|access| access.access_level)
+            .max()
+    });
+    match access_level {
+        None => Ok("not reported".to_string()),
+        Some(level) if level < DEVELOPER_ACCESS => {
+            Err(format!("access level {} is below Developer", level))
+        }
+        Some(_) => Ok(String::new()),
+    }
+}
+
+/// GitLab REST API v4, on gitlab.com or a self-managed instance. Artifacts go
+/// to the project's Generic Packages registry and are linked from the release
+pub struct GitlabBackend {
+    config: BackendConfig,
+    base_url: String,
+}
+
+impl GitlabBackend {
+    pub fn new(config: BackendConfig) -> Self {
+        let base_url = normalize_api_url(&config.api_url, API_PATH);
+        GitlabBackend { config, base_url }
+    }
+
+    /// `owner` may contain subgroups, so the id is the URL-encoded full path
+    fn project_url(&self) -> String {
+        format!(
+            "{}{}/projects/{}",
+            self.base_url,
+            API_PATH,
+            encode_segment(&format!("{}/{}", self.config.owner, self.config.repo))
+        )
+    }
+
+    fn release_url(&self, tag: &str) -> String {
+        format!("{}/{}", self.releases_url(), encode_segment(tag))
+    }
+
+    /// Generic package file for `filename`, versioned by the release tag
+    fn package_file_url(&self, tag: &str, filename: &str) -> String {
+        format!(
+            "{}/packages/generic/{}/{}/{}",
+            self.project_url(),
+            encode_segment(&self.config.repo),
+            encode_segment(&package_version(tag)),
+            encode_segment(filename)
+        )
+    }
+
+    fn tag_url(&self, tag: &str) -> String {
+        format!(
+            "{}/repository/tags/{}",
+            self.project_url(),
+            encode_segment(tag)
+        )
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        api_request(
+            &self.config,
+            method,
+            url,
+            &self.authorization(self.config.token.expose()),
+            "application/json",
+        )
+    }
+}
+
+#[async_trait]
+impl ReleaseBackend for GitlabBackend {
+    fn forge(&self) -> Forge {
+        Forge::Gitlab
+    }
+
+    fn repo_web_url(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.base_url, self.config.owner, self.config.repo
+        )
+    }
+
+    fn commit_url_prefix(&self) -> String {
+        format!("{}/-/commit", self.repo_web_url())
+    }
+
+    fn releases_url(&self) -> String {
+        format!("{}/releases", self.project_url())
+    }
+
+    fn assets_url(&self, release_id: &str) -> String {
+        format!(
+            "{}/packages/generic/{}/{}",
+            self.project_url(),
+            encode_segment(&self.config.repo),
+            release_id
+        )
+    }
+
+    fn authorization(&self, token: &str) -> String {
+        format!("Bearer {}", token)
+    }
+
+    async fn create_release(
+        &self,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        if release.draft || release.prerelease {
+            warn!("GitLab releases have no draft or prerelease flag, publishing a regular release");
+        }
+        let res = self
+            .request(Method::POST, &self.releases_url())
+            .json(&GitlabRelease {
+                tag_name: &release.tag_name,
+                git_ref: &release.target_commitish,
+                name: &release.name,
+                description: &release.body,
+            })
+            .send()
+            .await?;
+        Ok(read_json::<ReleaseResponse>(res, &self.config.token)
+            .await?
+            .into())
+    }
+
+    async fn list_releases(&self) -> Result<Vec<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let url = self.releases_url();
+        let releases: Vec<ReleaseResponse> = fetch_all_pages(
+            |page| {
+                self.request(Method::GET, &url).query(&[
+                    ("page", page.to_string()),
+                    ("per_page", PER_PAGE.to_string()),
+                ])
+            },
+            PER_PAGE,
+            &self.config.token,
+        )
+        .await?;
+        Ok(releases.into_iter().map(RemoteRelease::from).collect())
+    }
+
+    async fn find_release_by_tag(
+        &self,
+        tag: &str,
+    ) -> Result<Option<RemoteRelease>, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::GET, &self.release_url(tag))
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(
+            read_json::<Option<ReleaseResponse>>(res, &self.config.token)
+                .await?
+                .map(RemoteRelease::from),
+        )
+    }
+
+    async fn update_release(
+        &self,
+        existing: &RemoteRelease,
+        release: &Release,
+    ) -> Result<RemoteRelease, Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::PUT, &self.release_url(&existing.tag_name))
+            .json(&serde_json::json!({
+                "name": release.name,
+                "description": release.body,
+            }))
+            .send()
+            .await?;
+        Ok(read_json::<ReleaseResponse>(res, &self.config.token)
+            .await?
+            .into())
+    }
+
+    /// Publish the file as a generic package, then link it from the release
+    async fn upload_asset(
+        &self,
+        release: &RemoteRelease,
+        artifact: &LoadedArtifact,
+    ) -> Result<RemoteAsset, Box<dyn Error + Send + Sync>> {
+        let package_url = self.package_file_url(&release.tag_name, &artifact.filename);
+        info!("Uploading to: {}", package_url);
+
+        let res = self
+            .request(Method::PUT, &package_url)
+            .header(
+                header::CONTENT_TYPE,
+                artifact::content_type(&artifact.filename, &artifact.content),
+            )
+            .timeout(self.config.upload_timeout)
+            .body(artifact.content.clone())
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await?;
+
+        let res = self
+            .request(
+                Method::POST,
+                &format!("{}/assets/links", self.release_url(&release.tag_name)),
+            )
+            .json(&AssetLink {
+                name: &artifact.filename,
+                url: &package_url,
+                link_type: "package",
+            })
+            .send()
+            .await?;
+        let link: Option<AssetLinkResponse> = read_upload(res, &self.config.token).await?;
+        Ok(link.map(RemoteAsset::from).unwrap_or(RemoteAsset {
+            name: artifact.filename.clone(),
+            browser_download_url: package_url,
+            api_url: None,
+        }))
+    }
+
+    async fn download_asset(
+        &self,
+        asset: &RemoteAsset,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        download_file(
+            &self.config,
+            &self.authorization(self.config.token.expose()),
+            &asset.browser_download_url,
+        )
+        .await
+    }
+
+    async fn delete_release(
+        &self,
+        release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::DELETE, &self.release_url(&release.tag_name))
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
+
+    /// The generic package holding the release's files outlives the release
+    async fn delete_uploads(
+        &self,
+        release: &RemoteRelease,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::GET, &format!("{}/packages", self.project_url()))
+            .query(&[
+                ("package_type", "generic"),
+                ("package_name", self.config.repo.as_str()),
+                (
+                    "package_version",
+                    package_version(&release.tag_name).as_str(),
+                ),
+            ])
+            .send()
+            .await?;
+        let packages: Vec<PackageResponse> = read_json(res, &self.config.token).await?;
+        for package in packages {
+            let res = self
+                .request(
+                    Method::DELETE,
+                    &format!("{}/packages/{}", self.project_url(), package.id),
+                )
+                .send()
+                .await?;
+            expect_success(res, &self.config.token).await?;
+        }
+        Ok(())
+    }
+
+    async fn tag_exists(&self, tag: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let res = self.request(Method::GET, &self.tag_url(tag)).send().await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        expect_success(res, &self.config.token).await?;
+        Ok(true)
+    }
+
+    async fn delete_tag(&self, tag: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self
+            .request(Method::DELETE, &self.tag_url(tag))
+            .send()
+            .await?;
+        expect_success(res, &self.config.token).await
+    }
+
+    async fn preflight_checks(&self, tag_name: &str, target_commitish: &str) -> Vec<CheckOutcome> {
+        let endpoints = PreflightEndpoints {
+            user_url: Some(format!("{}{}/user", self.base_url, API_PATH)),
+            repo_url: self.project_url(),
+            repo_name: format!("{}/{}", self.config.owner, self.config.repo),
+            commit_url: format!(
+                "{}/repository/commits/{}",
+                self.project_url(),
+                encode_segment(target_commitish)
+            ),
+            push: developer_access,
+        };
+        let request = |url: &str| self.request(Method::GET, url);
+        run_preflight_checks(
+            self,
+            &request,
+            &self.config.token,
+            endpoints,
+            tag_name,
+            target_commitish,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::Token;
+    use mockito::{Matcher, Server};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn backend(url: &str) -> GitlabBackend {
+        GitlabBackend::new(BackendConfig {
+            api_url: format!("{}/api/v4", url),
+            owner: "group/sub".to_string(),
+            repo: "app".to_string(),
+            token: Token::new("glpat"),
+            client: reqwest::Client::new(),
+            upload_timeout: Duration::from_secs(5),
+        })
+    }
+
+    #[test]
+    fn test_urls() {
+        let backend = backend("https://gitlab.example.com");
+        assert_eq!(
+            backend.releases_url(),
+            "https://gitlab.example.com/api/v4/projects/group%2Fsub%2Fapp/releases"
+        );
+        assert_eq!(
+            backend.commit_url_prefix(),
+            "https://gitlab.example.com/group/sub/app/-/commit"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_release_and_link_package() {
+        let mut server = Server::new_async().await;
+        let project = "/api/v4/projects/group%2Fsub%2Fapp";
+        let create = server
+            .mock("POST", format!("{}/releases", project).as_str())
+            .match_header("Authorization", "Bearer glpat")
+            .match_body(Matcher::Json(serde_json::json!({
+                "tag_name": "v1.0.0",
+                "ref": "main",
+                "name": "v1.0.0",
+                "description": "notes"
+            })))
+            .with_status(201)
+            .with_body(r#"{"tag_name": "v1.0.0", "_links": {"self": "https://gitlab.example.com/group/sub/app/-/releases/v1.0.0"}}"#)
+            .create_async()
+            .await;
+        let package_path = format!("{}/packages/generic/app/v1.0.0/app.tar.gz", project);
+        let upload = server
+            .mock("PUT", package_path.as_str())
+            .match_body("data")
+            .with_status(201)
+            .create_async()
+            .await;
+        let link = server
+            .mock(
+                "POST",
+                format!("{}/releases/v1.0.0/assets/links", project).as_str(),
+            )
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "name": "app.tar.gz",
+                "url": format!("{}{}", server.url(), package_path),
+                "link_type": "package"
+            })))
+            .with_status(201)
+            .create_async()
+            .await;
+        let backend = backend(&server.url());
+
+        let release = backend
+            .create_release(&Release {
+                tag_name: "v1.0.0".to_string(),
+                target_commitish: "main".to_string(),
+                name: "v1.0.0".to_string(),
+                body: "notes".to_string(),
+                draft: false,
+                prerelease: false,
+            })
+            .await
+            .unwrap();
+        let artifact = LoadedArtifact {
+            filename: "app.tar.gz".to_string(),
+            content: b"data".to_vec(),
+            path: PathBuf::from("app.tar.gz"),
+        };
+        backend.upload_asset(&release, &artifact).await.unwrap();
+
+        assert_eq!(
+            release.html_url.as_deref(),
+            Some("https://gitlab.example.com/group/sub/app/-/releases/v1.0.0")
+        );
+        assert_eq!(release.id, None);
+        create.assert_async().await;
+        upload.assert_async().await;
+        link.assert_async().await;
+    }
+
+    #[test]
+    fn test_build_metadata_in_package_version() {
+        let backend = backend("https://gitlab.example.com");
+        assert_eq!(
+            backend.package_file_url("v1.0.0+build.5", "app.tar.gz"),
+            "https://gitlab.example.com/api/v4/projects/group%2Fsub%2Fapp/packages/generic/app/v1.0.0_build.5/app.tar.gz"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_uploads_and_tag() {
+        let mut server = Server::new_async().await;
+        let project = "/api/v4/projects/group%2Fsub%2Fapp";
+        let packages = server
+            .mock("GET", format!("{}/packages", project).as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("package_name".into(), "app".into()),
+                Matcher::UrlEncoded("package_version".into(), "v1.0.0".into()),
+            ]))
+            .with_status(200)
+            .with_body(r#"[{"id": 17}]"#)
+            .create_async()
+            .await;
+        let delete_package = server
+            .mock("DELETE", format!("{}/packages/17", project).as_str())
+            .with_status(204)
+            .create_async()
+            .await;
+        let delete_tag = server
+            .mock(
+                "DELETE",
+                format!("{}/repository/tags/v1.0.0", project).as_str(),
+            )
+            .with_status(204)
+            .create_async()
+            .await;
+        let backend = backend(&server.url());
+        let release = RemoteRelease {
+            tag_name: "v1.0.0".to_string(),
+            ..Default::default()
+        };
+
+        backend.delete_uploads(&release).await.unwrap();
+        backend.delete_tag("v1.0.0").await.unwrap();
+
+        packages.assert_async().await;
+        delete_package.assert_async().await;
+        delete_tag.assert_async().await;
+    }
+}
@@ -0,0 +1,109 @@
+use reqwest::{Certificate, Client, Proxy};
+use std::error::Error;
+use std::time::Duration;
+
+/// User-Agent sent with every API request
+pub const USER_AGENT: &str = concat!("easer/", env!("CARGO_PKG_VERSION"));
+
+/// Settings shared by every HTTP request easer makes
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub proxy: Option<String>,
+    pub ca_cert: Option<String>,
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    pub upload_timeout: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            proxy: None,
+            ca_cert: None,
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(60),
+            upload_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Build the client used for all API calls. The request timeout set here
+/// applies to everything except uploads, which override it per request
+pub fn build_client(config: &HttpConfig) -> Result<Client, Box<dyn Error + Send + Sync>> {
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.timeout);
+
+    if let Some(proxy) = &config.proxy {
+        builder = builder
+            .proxy(Proxy::all(proxy).map_err(|e| format!("Invalid proxy URL {}: {}", proxy, e))?);
+    }
+
+    if let Some(ca_path) = &config.ca_cert {
+        let pem = std::fs::read(ca_path)
+            .map_err(|e| format!("Failed to read CA bundle {}: {}", ca_path, e))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid CA bundle {}: {}", ca_path, e))?;
+        if certs.is_empty() {
+            return Err(format!("No certificates found in CA bundle {}", ca_path).into());
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn config() -> HttpConfig {
+        HttpConfig {
+            proxy: None,
+            ca_cert: None,
+            connect_timeout: Duration::from_secs(1),
+            timeout: Duration::from_secs(5),
+            upload_timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_sends_user_agent() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .match_header("user-agent", USER_AGENT)
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let client = build_client(&config()).unwrap();
+        client.get(server.url()).send().await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_invalid_ca_bundle() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"not a certificate")
+            .unwrap();
+
+        let result = build_client(&HttpConfig {
+            ca_cert: Some(path.to_str().unwrap().to_string()),
+            ..config()
+        });
+
+        assert!(result.is_err());
+    }
+}
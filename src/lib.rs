@@ -0,0 +1,34 @@
+//! Create releases on Gitee, GitHub, Gitea/Forgejo and GitLab.
+//!
+//! [`ReleaseRequest`] publishes a release with generated notes and artifacts
+//! to one or more [`ReleaseTarget`]s, [`ChangelogGenerator`] renders notes and
+//! the next version from Conventional Commits, and [`backend::connect`] gives
+//! a [`ReleaseBackend`] client for a single repository. The `easer` binary is
+//! a thin command line wrapper over this API.
+
+pub mod artifact;
+pub mod backend;
+pub mod checksum;
+pub mod ci;
+pub mod conventional;
+pub mod error;
+pub mod gitea;
+pub mod gitee;
+pub mod github;
+pub mod gitlab;
+pub mod http;
+pub mod messages;
+pub mod output;
+mod preflight;
+pub mod release;
+pub mod remote;
+pub mod sign;
+pub mod token;
+
+pub use backend::{ClientConfig, Forge, ReleaseBackend};
+pub use conventional::ChangelogGenerator;
+pub use error::{EaserError, ErrorKind};
+pub use http::HttpConfig;
+pub use output::{AssetReport, ReleaseReport};
+pub use release::{ReleaseOutcome, ReleaseRequest, ReleaseTarget};
+pub use token::Token;
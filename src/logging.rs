@@ -0,0 +1,82 @@
+use crate::args::LogArgs;
+use clap::ValueEnum;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+/// How log lines are written to stderr
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the enclosing spans
+    Json,
+}
+
+/// `info` by default, each `-v` one level more and each `-q` one level less
+fn level(args: &LogArgs) -> LevelFilter {
+    match i16::from(args.verbose) - i16::from(args.quiet) {
+        ..=-3 => LevelFilter::OFF,
+        -2 => LevelFilter::ERROR,
+        -1 => LevelFilter::WARN,
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Directives in `RUST_LOG`, e.g. `easer=debug,reqwest=trace`, replace the
+/// level given by `-v`/`-q`
+fn filter(args: &LogArgs, directives: Option<&str>) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(level(args).into())
+        .parse_lossy(directives.unwrap_or_default())
+}
+
+/// Install the global subscriber. Logs always go to stderr, stdout is reserved
+/// for results
+pub fn init(args: &LogArgs) {
+    let directives = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter(args, directives.as_deref()))
+        .with_writer(std::io::stderr);
+    match args.log_format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_args(verbose: u8, quiet: u8) -> LogArgs {
+        LogArgs {
+            verbose,
+            quiet,
+            log_format: LogFormat::Text,
+        }
+    }
+
+    #[test]
+    fn test_log_level() {
+        assert_eq!(level(&log_args(0, 0)), LevelFilter::INFO);
+        assert_eq!(level(&log_args(1, 0)), LevelFilter::DEBUG);
+        assert_eq!(level(&log_args(3, 0)), LevelFilter::TRACE);
+        assert_eq!(level(&log_args(0, 2)), LevelFilter::ERROR);
+        assert_eq!(level(&log_args(0, 5)), LevelFilter::OFF);
+    }
+
+    #[test]
+    fn test_rust_log_replaces_level() {
+        let args = log_args(2, 0);
+        assert_eq!(
+            filter(&args, None).max_level_hint(),
+            Some(LevelFilter::TRACE)
+        );
+        assert_eq!(
+            filter(&args, Some("easer=warn")).max_level_hint(),
+            Some(LevelFilter::WARN)
+        );
+    }
+}
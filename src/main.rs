@@ -1,25 +1,156 @@
 mod args;
-mod conventional;
-mod gitee;
+mod commands;
+mod completions;
+mod config;
+mod logging;
+mod mirror;
+mod profile;
+mod review;
 
-use args::Args;
-use clap::Parser;
-use gitee::create_release;
+use args::{Args, Cli, Command, ConfigCommand};
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use commands::create_release;
+use easer::backend::Forge;
+use easer::error::EaserError;
+use easer::{artifact, gitee, http, sign, token};
 use std::error::Error;
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
+use std::path::Path;
+
+/// Exit code of `easer next-version` when no commit since the last release
+/// calls for a new version
+const NOTHING_TO_RELEASE: i32 = 3;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Init tracing subscriber
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO) // Set the log level to INFO
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("Setting default subscriber failed");
+async fn main() {
+    // Before anything is logged: stdout belongs to the shell during completion
+    completions::handle_request();
+
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(&cli.log);
+    if let Err(e) = run(cli, &matches).await {
+        // Wrappers tell failures apart by the exit code, see `ErrorKind::exit_code`
+        let e = EaserError::from(e);
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
 
-    let args = Args::parse();
+async fn run(cli: Cli, matches: &ArgMatches) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // The release options live on the subcommand's matches, or on the top
+    // level for the flat invocation
+    let sub_matches = matches.subcommand().map_or(matches, |(_, m)| m);
 
-    create_release(args, None).await?;
+    match cli.command {
+        None => {
+            let output = cli.release.output;
+            let args = prepare(cli.release, matches)
+                .await
+                .map_err(|e| commands::early_failure(output, e))?;
+            let api_url = args.api_url.clone();
+            create_release(args, api_url.as_deref()).await?;
+        }
+        Some(Command::Release(args)) => {
+            let output = args.output;
+            let args = prepare(args, sub_matches)
+                .await
+                .map_err(|e| commands::early_failure(output, e))?;
+            let api_url = args.api_url.clone();
+            create_release(args, api_url.as_deref()).await?;
+        }
+        // `easer check` takes the same arguments as a release but stops after pre-flight
+        Some(Command::Check(args)) => {
+            let args = prepare(args, sub_matches).await?;
+            let api_url = args.api_url.clone();
+            commands::check_release(args, api_url.as_deref()).await?;
+        }
+        Some(Command::Upload(upload)) => {
+            let output = upload.args.output;
+            let args = prepare(upload.args, sub_matches)
+                .await
+                .map_err(|e| commands::early_failure(output, e))?;
+            let api_url = args.api_url.clone();
+            commands::upload_release_assets(args, &upload.tag, &upload.files, api_url.as_deref())
+                .await?;
+        }
+        Some(Command::Changelog(mut args)) => {
+            config::layer_args(&mut args, sub_matches)?;
+            let api_url = args.api_url.clone();
+            commands::print_changelog(&args, api_url.as_deref())?;
+        }
+        Some(Command::NextVersion(mut next)) => {
+            config::layer_args(&mut next.args, sub_matches)?;
+            if !commands::print_next_version(&next.args, next.json)? {
+                std::process::exit(NOTHING_TO_RELEASE);
+            }
+        }
+        Some(Command::Lint(mut args)) => {
+            config::layer_args(&mut args, sub_matches)?;
+            commands::lint_commits(&args)?;
+        }
+        // `easer verify` works offline
+        Some(Command::Verify(args)) => {
+            sign::verify_release_dir(
+                Path::new(&args.dir),
+                args.method,
+                args.key.as_deref(),
+                args.identity.as_deref(),
+            )
+            .await?;
+        }
+        Some(Command::Login(args)) => profile::login(args).await?,
+        Some(Command::Mirror(args)) => mirror::mirror(args).await?,
+        Some(Command::Completions(args)) => completions::print_completions(args.shell)?,
+        Some(Command::Man(args)) => completions::print_man(args.out_dir.as_deref())?,
+        Some(Command::Config(ConfigCommand::Show(mut args))) => {
+            let show_matches = sub_matches.subcommand().map_or(sub_matches, |(_, m)| m);
+            config::show(&config::layer_args(&mut args, show_matches)?);
+        }
+    }
 
     Ok(())
 }
+
+/// Layer the config files under the release options, apply the profile, expand
+/// artifact patterns and, for Gitee, probe the API before anything is written
+async fn prepare(
+    mut args: Args,
+    matches: &ArgMatches,
+) -> Result<Args, Box<dyn Error + Send + Sync>> {
+    config::layer_args(&mut args, matches)?;
+    if let Some(patterns) = &args.artifacts {
+        args.artifacts = Some(artifact::expand_patterns(patterns));
+    }
+    // A profile fills in whatever the command line did not set explicitly
+    if let Some(name) = &args.profile {
+        let profile = profile::load_profile(name, args.forge)?;
+        if args.token.is_none() {
+            args.token = Some(token::Token::new(profile.token));
+        }
+        if args.api_url.is_none() {
+            args.api_url = profile.api_url;
+        }
+    }
+    if args.api_url.is_none() && args.forge == Forge::Gitee {
+        args.api_url = gitee::api_url_from_env();
+    }
+
+    // A dry run stops before the network, so it needs neither a token nor the probe
+    if !args.dry_run && args.forge == Forge::Gitee {
+        if let (Some(url), Some(owner), Some(repo)) =
+            (args.api_url.as_deref(), &args.owner, &args.repo)
+        {
+            let token = token::resolve_token(
+                args.token.as_ref(),
+                args.token_file.as_deref(),
+                args.forge.token_env(),
+                &gitee::web_url(url),
+                &args.repo_path,
+            )?;
+            args.token = Some(token.clone());
+            let client = http::build_client(&http::HttpConfig::from(&args.http))?;
+            gitee::probe_api(&client, url, owner, repo, &token, &args.lang).await?;
+        }
+    }
+    Ok(args)
+}
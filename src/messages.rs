@@ -0,0 +1,141 @@
+/// User-facing messages in the language selected by `--lang`
+pub struct Messages<'a> {
+    pub invalid_tag: &'a str,
+    pub success: &'a str,
+    pub failure: &'a str,
+    pub api_error: &'a str,
+    pub upload_start: &'a str,
+    pub upload_success: &'a str,
+    pub upload_failure: &'a str,
+    pub file_read_error: &'a str,
+    pub checksum_written: &'a str,
+    pub signed: &'a str,
+    pub api_unreachable: &'a str,
+    pub api_mismatch: &'a str,
+    pub preflight_token: &'a str,
+    pub preflight_repo: &'a str,
+    pub preflight_push: &'a str,
+    pub preflight_target: &'a str,
+    pub preflight_tag: &'a str,
+    pub preflight_release: &'a str,
+    pub preflight_artifact: &'a str,
+    pub preflight_failed: &'a str,
+    pub uploads_failed: &'a str,
+    pub target_skipped: &'a str,
+    pub targets_failed: &'a str,
+    pub rollback: &'a str,
+    pub rollback_failure: &'a str,
+    pub mirror_copy: &'a str,
+    pub mirror_up_to_date: &'a str,
+    pub mirror_asset: &'a str,
+    pub mirror_skip_draft: &'a str,
+    pub mirror_missing_tag: &'a str,
+    pub mirror_failed: &'a str,
+    pub release_not_found: &'a str,
+    pub lint_invalid: &'a str,
+    pub lint_passed: &'a str,
+    pub lint_failed: &'a str,
+    pub review_version: &'a str,
+    pub review_title: &'a str,
+    pub review_prompt: &'a str,
+    pub review_bump_prompt: &'a str,
+    pub review_notes_saved: &'a str,
+    pub review_notes_reused: &'a str,
+    pub review_aborted: &'a str,
+}
+
+impl<'a> Messages<'a> {
+    pub fn new(lang: &str) -> Self {
+        match lang {
+            "en-us" => Messages {
+                invalid_tag: "Invalid semantic version tag name",
+                success: "Release created successfully",
+                failure: "Failed to create release",
+                api_error: "API request failed with status",
+                upload_start: "Uploading artifact",
+                upload_success: "Successfully uploaded artifact",
+                upload_failure: "Failed to upload artifact",
+                file_read_error: "Failed to read artifact file",
+                checksum_written: "Wrote checksum manifest",
+                signed: "Signed artifact",
+                api_unreachable: "Could not reach Gitee API at",
+                api_mismatch: "Endpoint does not serve Gitee API v5, check --api-url:",
+                preflight_token: "Access token is valid",
+                preflight_repo: "Repository exists",
+                preflight_push: "Token can push to the repository",
+                preflight_target: "Target commit or branch exists on the remote",
+                preflight_tag: "Tag does not exist yet",
+                preflight_release: "Release does not exist yet",
+                preflight_artifact: "Artifact is readable",
+                preflight_failed: "Pre-flight checks failed",
+                uploads_failed: "uploads failed",
+                target_skipped: "skipped after an earlier failure",
+                targets_failed: "Release failed on some targets",
+                rollback: "Rolling back release on",
+                rollback_failure: "Failed to roll back release on",
+                mirror_copy: "Mirroring release",
+                mirror_up_to_date: "Release already mirrored",
+                mirror_asset: "Copying asset",
+                mirror_skip_draft: "Skipping draft release",
+                mirror_missing_tag: "Tag not found on the target, push the tags first",
+                mirror_failed: "Mirroring failed for some releases or assets",
+                release_not_found: "No release found for tag",
+                lint_invalid: "Not a Conventional Commit",
+                lint_passed: "All commits follow Conventional Commits",
+                lint_failed: "Commits not following Conventional Commits",
+                review_version: "Version",
+                review_title: "Title",
+                review_prompt: "[a]ccept, [e]dit notes, change [b]ump or [q]uit?",
+                review_bump_prompt: "New bump level (major, minor, patch):",
+                review_notes_saved: "Saved release notes to",
+                review_notes_reused: "Using release notes saved by a previous run",
+                review_aborted: "Release aborted",
+            },
+            _ => Messages {
+                // Default to zh-cn
+                invalid_tag: "无效的语义化版本标签名称",
+                success: "版本发布成功创建",
+                failure: "创建版本发布失败",
+                api_error: "API 请求失败，状态码",
+                upload_start: "开始上传 artifact",
+                upload_success: "成功上传 artifact",
+                upload_failure: "上传 artifact 失败",
+                file_read_error: "读取 artifact 文件失败",
+                checksum_written: "已写入校验和清单",
+                signed: "已签名 artifact",
+                api_unreachable: "无法连接 Gitee API",
+                api_mismatch: "该地址未提供 Gitee API v5，请检查 --api-url:",
+                preflight_token: "访问令牌有效",
+                preflight_repo: "仓库存在",
+                preflight_push: "令牌具有仓库推送权限",
+                preflight_target: "目标提交或分支在远程仓库中存在",
+                preflight_tag: "标签尚未存在",
+                preflight_release: "Release 尚未存在",
+                preflight_artifact: "artifact 可读取",
+                preflight_failed: "发布前检查未通过",
+                uploads_failed: "个附件上传失败",
+                target_skipped: "因前一个目标失败而跳过",
+                targets_failed: "部分目标发布失败",
+                rollback: "正在回滚 Release",
+                rollback_failure: "回滚 Release 失败",
+                mirror_copy: "正在同步 Release",
+                mirror_up_to_date: "Release 已同步",
+                mirror_asset: "正在复制附件",
+                mirror_skip_draft: "跳过草稿 Release",
+                mirror_missing_tag: "目标仓库中没有该标签，请先推送标签",
+                mirror_failed: "部分 Release 或附件同步失败",
+                release_not_found: "未找到该标签对应的 Release",
+                lint_invalid: "不符合约定式提交规范",
+                lint_passed: "所有提交均符合约定式提交规范",
+                lint_failed: "不符合约定式提交规范的提交数",
+                review_version: "版本",
+                review_title: "标题",
+                review_prompt: "[a] 发布  [e] 编辑说明  [b] 更改版本级别  [q] 取消？",
+                review_bump_prompt: "新的版本级别（major、minor、patch）：",
+                review_notes_saved: "发布说明已保存到",
+                review_notes_reused: "使用上次运行保存的发布说明",
+                review_aborted: "已取消发布",
+            },
+        }
+    }
+}
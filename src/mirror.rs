@@ -0,0 +1,306 @@
+use crate::args::MirrorArgs;
+use easer::artifact::LoadedArtifact;
+use easer::backend::{
+    self, ClientConfig, Release, ReleaseBackend, RemoteAsset, RemoteRelease, RepoRef,
+};
+use easer::http::HttpConfig;
+use easer::messages::Messages;
+use easer::token::{self, Token};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use tracing::{error, info};
+
+/// Build the backend for one side of the mirror. A public source can be read
+/// without a token, the target cannot
+fn connect(
+    repo: &RepoRef,
+    api_url: Option<&str>,
+    token_env: Option<&str>,
+    token_file: Option<&str>,
+    http_config: &HttpConfig,
+    token_required: bool,
+) -> Result<Box<dyn ReleaseBackend>, Box<dyn Error + Send + Sync>> {
+    let build = |token: Token| {
+        backend::connect(&ClientConfig {
+            forge: repo.forge,
+            api_url: api_url.map(str::to_string),
+            owner: repo.owner.clone(),
+            repo: repo.repo.clone(),
+            token,
+            http: http_config.clone(),
+        })
+    };
+    let anonymous = build(Token::new(""))?;
+    match token::resolve_token(
+        None,
+        token_file,
+        token_env.unwrap_or(repo.forge.token_env()),
+        &anonymous.repo_web_url(),
+        ".",
+    ) {
+        Ok(token) => build(token),
+        Err(e) if token_required => Err(e),
+        Err(_) => {
+            info!("No access token for {}, reading it anonymously", repo);
+            Ok(anonymous)
+        }
+    }
+}
+
+/// Source assets the target release does not have yet, matched by name
+fn missing_assets<'a>(
+    source: &'a RemoteRelease,
+    target: Option<&RemoteRelease>,
+) -> Vec<&'a RemoteAsset> {
+    source
+        .assets
+        .iter()
+        .filter(|asset| {
+            !asset.name.is_empty()
+                && !target.is_some_and(|t| t.assets.iter().any(|a| a.name == asset.name))
+        })
+        .collect()
+}
+
+/// `easer mirror`: copy every release of `--from` that `--to` lacks, oldest
+/// first, then fill in assets missing from releases that already exist. A run
+/// interrupted half-way is completed by the next one. Drafts are not copied:
+/// Gitee and GitLab have no drafts and would publish them
+pub async fn mirror(args: MirrorArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let messages = Messages::new(&args.lang);
+    let http_config = HttpConfig::from(&args.http);
+    let source = connect(
+        &args.from,
+        args.from_api_url.as_deref(),
+        args.from_token_env.as_deref(),
+        args.from_token_file.as_deref(),
+        &http_config,
+        false,
+    )?;
+    let target = connect(
+        &args.to,
+        args.to_api_url.as_deref(),
+        args.to_token_env.as_deref(),
+        args.to_token_file.as_deref(),
+        &http_config,
+        !args.dry_run,
+    )?;
+
+    let source_releases = source.list_releases().await?;
+    let target_releases: HashMap<String, RemoteRelease> = target
+        .list_releases()
+        .await?
+        .into_iter()
+        .map(|release| (release.tag_name.clone(), release))
+        .collect();
+
+    let mut failures = 0;
+    for source_release in source_releases.iter().rev() {
+        let tag = &source_release.tag_name;
+        if source_release.draft {
+            info!("{}: {}", messages.mirror_skip_draft, tag);
+            continue;
+        }
+        let existing = target_releases.get(tag);
+        let assets = missing_assets(source_release, existing);
+        if existing.is_some() && assets.is_empty() {
+            info!("{}: {}", messages.mirror_up_to_date, tag);
+            continue;
+        }
+
+        if args.dry_run {
+            println!(
+                "{} {} ({} assets)",
+                if existing.is_some() {
+                    "UPDATE"
+                } else {
+                    "CREATE"
+                },
+                tag,
+                assets.len()
+            );
+            for asset in &assets {
+                println!("  {}", asset.name);
+            }
+            continue;
+        }
+
+        info!("{}: {}", messages.mirror_copy, tag);
+        let release = match existing {
+            Some(release) => release.clone(),
+            None => {
+                // Creating the release would otherwise tag the head of the
+                // source's branch, not the commit the source release is at
+                match target.tag_exists(tag).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        error!("{}: {}", messages.mirror_missing_tag, tag);
+                        failures += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        error!("{}: {} - {}", messages.failure, tag, e);
+                        failures += 1;
+                        continue;
+                    }
+                }
+                let created = target
+                    .create_release(&Release {
+                        tag_name: tag.clone(),
+                        target_commitish: tag.clone(),
+                        name: source_release.name.clone(),
+                        body: source_release.body.clone(),
+                        draft: false,
+                        prerelease: source_release.prerelease,
+                    })
+                    .await;
+                match created {
+                    Ok(created) => RemoteRelease {
+                        tag_name: tag.clone(),
+                        ..created
+                    },
+                    Err(e) => {
+                        error!("{}: {} - {}", messages.failure, tag, e);
+                        failures += 1;
+                        continue;
+                    }
+                }
+            }
+        };
+
+        for asset in assets {
+            info!("{}: {} ({})", messages.mirror_asset, asset.name, tag);
+            let content = match source.download_asset(asset).await {
+                Ok(content) => content,
+                Err(e) => {
+                    error!("{}: {} - {}", messages.upload_failure, asset.name, e);
+                    failures += 1;
+                    continue;
+                }
+            };
+            let artifact = LoadedArtifact {
+                filename: asset.name.clone(),
+                content,
+                path: PathBuf::from(&asset.name),
+            };
+            if let Err(e) = target.upload_asset(&release, &artifact).await {
+                error!("{}: {} - {}", messages.upload_failure, asset.name, e);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        let err_msg = format!("{}: {}", messages.mirror_failed, failures);
+        error!("{}", err_msg);
+        return Err(err_msg.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::HttpArgs;
+    use mockito::{Matcher, Server};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_mirror_copies_missing_release_and_assets() {
+        let mut server = Server::new_async().await;
+        let source_list = server
+            .mock("GET", "/repos/up/tool/releases")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(format!(
+                r#"[
+                    {{"id": 3, "tag_name": "v1.2.0", "name": "v1.2.0", "body": "wip", "draft": true, "prerelease": false,
+                      "assets": [{{"name": "tool.tar.gz", "browser_download_url": "{url}/dl/draft.tar.gz"}}]}},
+                    {{"id": 2, "tag_name": "v1.1.0", "target_commitish": "main", "name": "v1.1.0", "body": "new", "draft": false, "prerelease": true,
+                      "assets": [{{"name": "tool.tar.gz", "browser_download_url": "{url}/dl/tool.tar.gz"}}]}},
+                    {{"id": 1, "tag_name": "v1.0.0", "name": "v1.0.0", "body": null, "draft": false, "prerelease": false,
+                      "assets": [{{"name": "tool.tar.gz", "browser_download_url": "{url}/dl/old.tar.gz"}}]}}
+                ]"#,
+                url = server.url()
+            ))
+            .create_async()
+            .await;
+        let target_list = server
+            .mock("GET", "/api/v5/repos/mirror/tool/releases")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(
+                r#"[{"id": 7, "tag_name": "v1.0.0", "assets": [{"name": "tool.tar.gz", "browser_download_url": "x"}]}]"#,
+            )
+            .create_async()
+            .await;
+        let download = server
+            .mock("GET", "/dl/tool.tar.gz")
+            .match_header("Authorization", "Bearer upstream_token")
+            .with_status(200)
+            .with_body("archive")
+            .create_async()
+            .await;
+        let target_tags = server
+            .mock("GET", "/api/v5/repos/mirror/tool/tags")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(r#"[{"name": "v1.0.0"}, {"name": "v1.1.0"}]"#)
+            .create_async()
+            .await;
+        let create = server
+            .mock("POST", "/api/v5/repos/mirror/tool/releases")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"tag_name": "v1.1.0", "target_commitish": "v1.1.0", "body": "new", "draft": false, "prerelease": true}"#
+                    .to_string(),
+            ))
+            .with_status(201)
+            .with_body(r#"{"id": 8}"#)
+            .create_async()
+            .await;
+        let upload = server
+            .mock("POST", "/api/v5/repos/mirror/tool/releases/8/attach_files")
+            .match_header("Authorization", "token mirror_token")
+            .match_body(Matcher::Regex("archive".to_string()))
+            .with_status(201)
+            .create_async()
+            .await;
+
+        let dir = tempdir().unwrap();
+        let token_path = dir.path().join("token");
+        std::fs::write(&token_path, "mirror_token").unwrap();
+        std::env::set_var("EASER_TEST_UPSTREAM_TOKEN", "upstream_token");
+        let args = MirrorArgs {
+            from: "github:up/tool".parse().unwrap(),
+            to: "gitee:mirror/tool".parse().unwrap(),
+            from_api_url: Some(server.url()),
+            to_api_url: Some(server.url()),
+            from_token_file: None,
+            to_token_file: Some(token_path.to_str().unwrap().to_string()),
+            from_token_env: Some("EASER_TEST_UPSTREAM_TOKEN".to_string()),
+            to_token_env: None,
+            lang: "en-us".to_string(),
+            dry_run: false,
+            http: HttpArgs::default(),
+        };
+        let result = mirror(args).await;
+
+        assert!(result.is_ok(), "mirror failed: {:?}", result.err());
+        source_list.assert_async().await;
+        target_list.assert_async().await;
+        download.assert_async().await;
+        target_tags.assert_async().await;
+        create.assert_async().await;
+        upload.assert_async().await;
+    }
+
+    #[test]
+    fn test_repo_ref_parse() {
+        let repo: RepoRef = "gitlab:group/sub/app".parse().unwrap();
+        assert_eq!(repo.owner, "group/sub");
+        assert_eq!(repo.repo, "app");
+        assert!("github/owner/repo".parse::<RepoRef>().is_err());
+        assert!("bitbucket:o/r".parse::<RepoRef>().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::{EaserError, ErrorKind};
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How `easer release` reports its result on stdout
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable log lines and summaries
+    #[default]
+    Text,
+    /// One JSON document once the release is done; logs stay on stderr
+    Json,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadStatus {
+    Uploaded,
+    Failed,
+}
+
+/// Outcome of uploading one artifact
+#[derive(Serialize, Debug, Clone)]
+pub struct AssetReport {
+    pub name: String,
+    pub size: usize,
+    /// `None` when the upload failed or the forge did not report a URL
+    pub download_url: Option<String>,
+    pub status: UploadStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of publishing to one target
+#[derive(Serialize, Debug, Clone)]
+pub struct ReleaseReport {
+    /// e.g. `Gitee owner/repo`
+    pub target: String,
+    /// `None` when the release could not be created
+    pub id: Option<i64>,
+    pub html_url: Option<String>,
+    pub tag: String,
+    pub name: String,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub assets: Vec<AssetReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Category of `error`, e.g. `tag_conflict`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+}
+
+impl ReleaseReport {
+    pub fn failed_uploads(&self) -> usize {
+        self.assets
+            .iter()
+            .filter(|asset| asset.status == UploadStatus::Failed)
+            .count()
+    }
+}
+
+/// Print the result document: an object for a single target, an array when
+/// publishing to several
+pub fn print_json(
+    reports: &[ReleaseReport],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let json = match reports {
+        [report] => serde_json::to_string_pretty(report)?,
+        _ => serde_json::to_string_pretty(reports)?,
+    };
+    println!("{}", json);
+    Ok(())
+}
+
+/// The document printed instead of the reports when the command fails before
+/// any release is attempted, e.g. in pre-flight or on an invalid config
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorReport {
+    pub error: String,
+    pub kind: ErrorKind,
+    pub exit_code: i32,
+}
+
+impl From<&EaserError> for ErrorReport {
+    fn from(e: &EaserError) -> Self {
+        ErrorReport {
+            error: e.to_string(),
+            kind: e.kind(),
+            exit_code: e.exit_code(),
+        }
+    }
+}
+
+pub fn print_error_json(e: &EaserError) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    println!("{}", serde_json::to_string_pretty(&ErrorReport::from(e))?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_report_json() {
+        let report = ReleaseReport {
+            target: "Gitee acme/tool".to_string(),
+            id: Some(7),
+            html_url: Some("https://gitee.com/acme/tool/releases/v1.0.0".to_string()),
+            tag: "v1.0.0".to_string(),
+            name: "Release 1.0.0".to_string(),
+            draft: false,
+            prerelease: true,
+            assets: vec![
+                AssetReport {
+                    name: "tool.tar.gz".to_string(),
+                    size: 42,
+                    download_url: Some("https://gitee.com/dl/tool.tar.gz".to_string()),
+                    status: UploadStatus::Uploaded,
+                    error: None,
+                },
+                AssetReport {
+                    name: "tool.zip".to_string(),
+                    size: 9,
+                    download_url: None,
+                    status: UploadStatus::Failed,
+                    error: Some("500 Internal Server Error".to_string()),
+                },
+            ],
+            error: None,
+            error_kind: None,
+        };
+
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(report.failed_uploads(), 1);
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["prerelease"], true);
+        assert_eq!(json["assets"][0]["status"], "uploaded");
+        assert_eq!(json["assets"][1]["download_url"], serde_json::Value::Null);
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn test_error_report_json() {
+        let e = EaserError::TagConflict("v1.0.0 already exists".to_string());
+
+        let json = serde_json::to_value(ErrorReport::from(&e)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "error": "v1.0.0 already exists",
+                "kind": "tag_conflict",
+                "exit_code": 8
+            })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::artifact::ArtifactSpec;
+use crate::backend::{CheckKind, ReleaseBackend};
+use crate::error::{EaserError, ErrorKind};
+use crate::messages::Messages;
+use std::error::Error;
+use tracing::error;
+
+struct Check {
+    label: String,
+    kind: ErrorKind,
+    outcome: Result<String, String>,
+}
+
+fn label<'a>(kind: CheckKind, messages: &Messages<'a>) -> &'a str {
+    match kind {
+        CheckKind::Token => messages.preflight_token,
+        CheckKind::Repository => messages.preflight_repo,
+        CheckKind::PushPermission => messages.preflight_push,
+        CheckKind::Target => messages.preflight_target,
+        CheckKind::TagAbsent => messages.preflight_tag,
+        CheckKind::ReleaseAbsent => messages.preflight_release,
+    }
+}
+
+/// What a failed check means for the exit code
+fn error_kind(kind: CheckKind) -> ErrorKind {
+    match kind {
+        CheckKind::Token | CheckKind::PushPermission => ErrorKind::Auth,
+        CheckKind::Repository | CheckKind::Target => ErrorKind::NotFound,
+        CheckKind::TagAbsent | CheckKind::ReleaseAbsent => ErrorKind::TagConflict,
+    }
+}
+
+fn check_artifact(spec: &str) -> Result<String, String> {
+    let path = ArtifactSpec::parse(spec).path;
+    if !path.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    std::fs::File::open(&path)
+        .map(|_| path.display().to_string())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Validate everything that would make the release fail half-way, print a
+/// checklist to stderr and return an error if any check failed. Nothing is written.
+/// The error has the kind of the first failed check
+pub async fn run_preflight(
+    backend: &dyn ReleaseBackend,
+    tag_name: &str,
+    target_commitish: &str,
+    artifacts: &[String],
+    messages: &Messages<'_>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut checks: Vec<Check> = backend
+        .preflight_checks(tag_name, target_commitish)
+        .await
+        .into_iter()
+        .map(|check| Check {
+            label: label(check.kind, messages).to_string(),
+            kind: error_kind(check.kind),
+            outcome: check.outcome,
+        })
+        .collect();
+    for spec in artifacts {
+        checks.push(Check {
+            label: messages.preflight_artifact.to_string(),
+            kind: ErrorKind::Other,
+            outcome: check_artifact(spec),
+        });
+    }
+
+    let mut failed = 0;
+    let mut kind = None;
+    for check in &checks {
+        match &check.outcome {
+            Ok(detail) if detail.is_empty() => eprintln!("[✓] {}", check.label),
+            Ok(detail) => eprintln!("[✓] {} ({})", check.label, detail),
+            Err(reason) => {
+                failed += 1;
+                kind.get_or_insert(check.kind);
+                eprintln!("[✗] {}: {}", check.label, reason);
+            }
+        }
+    }
+
+    if failed > 0 {
+        let err_msg = format!("{}: {}/{}", messages.preflight_failed, failed, checks.len());
+        error!("{}", err_msg);
+        return Err(EaserError::new(kind.unwrap_or(ErrorKind::Other), err_msg).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use crate::args::LoginArgs;
+use easer::backend::{normalize_api_url, Forge};
+use easer::error::EaserError;
+use easer::gitee::{self, API_PATH, DEFAULT_API_URL};
+use easer::http::{self, HttpConfig};
+use easer::token::Token;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const CREDENTIALS_FILE: &str = "credentials.toml";
+
+/// Credentials stored by `easer login` for one profile
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub api_url: Option<String>,
+    pub token: String,
+    pub login: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Credentials {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Deserialize)]
+struct GiteeUser {
+    login: String,
+}
+
+/// `$XDG_CONFIG_HOME/easer`, falling back to `~/.config/easer`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("easer"))
+}
+
+pub fn credentials_path() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    config_dir()
+        .map(|dir| dir.join(CREDENTIALS_FILE))
+        .ok_or_else(|| {
+            EaserError::Config(
+                "Cannot locate config directory: neither XDG_CONFIG_HOME nor HOME is set"
+                    .to_string(),
+            )
+            .into()
+        })
+}
+
+impl Credentials {
+    /// Load the credentials file, treating a missing file as empty
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| {
+                EaserError::Config(format!(
+                    "Invalid credentials file {}: {}",
+                    path.display(),
+                    e
+                ))
+                .into()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Credentials::default()),
+            Err(e) => {
+                Err(EaserError::Config(format!("Failed to read {}: {}", path.display(), e)).into())
+            }
+        }
+    }
+
+    /// Write the credentials file, readable by the owner only
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = toml::to_string_pretty(self)?;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // `mode` only applies on creation, so tighten an existing file as well
+            if path.exists() {
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        std::io::Write::write_all(&mut options.open(path)?, content.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Look up a profile stored by `easer login` for a release on `forge`.
+/// `easer login` only verifies Gitee tokens, so a profile is refused for any
+/// other forge rather than sending its token and API URL there
+pub fn load_profile(name: &str, forge: Forge) -> Result<Profile, Box<dyn Error + Send + Sync>> {
+    if forge != Forge::Gitee {
+        return Err(EaserError::Config(format!(
+            "Profile '{}' holds Gitee credentials and cannot be used to release on {}",
+            name,
+            forge.display_name()
+        ))
+        .into());
+    }
+    let path = credentials_path()?;
+    Credentials::load(&path)?
+        .profiles
+        .remove(name)
+        .ok_or_else(|| {
+            EaserError::Config(format!(
+                "Profile '{}' not found in {}, run `easer login --profile {}` first",
+                name,
+                path.display(),
+                name
+            ))
+            .into()
+        })
+}
+
+/// Check the token against `GET /api/v5/user` and return the account login
+pub async fn verify_token(
+    client: &reqwest::Client,
+    api_base_url: &str,
+    token: &Token,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}{}/user",
+        normalize_api_url(api_base_url, API_PATH),
+        API_PATH
+    );
+    let res = client
+        .get(&url)
+        .header(header::AUTHORIZATION, format!("token {}", token.expose()))
+        .header(header::ACCEPT, "application/json")
+        .send()
+        .await?;
+    let status = res.status();
+    if !status.is_success() {
+        let body = token.redact(&res.text().await.unwrap_or_default());
+        return Err(
+            EaserError::Auth(format!("Token rejected by {}: {} - {}", url, status, body)).into(),
+        );
+    }
+    let user: GiteeUser = res.json().await?;
+    Ok(user.login)
+}
+
+fn read_token(api_base_url: &str) -> Result<Token, Box<dyn Error + Send + Sync>> {
+    let value = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("Gitee access token for {}: ", api_base_url))?
+    } else {
+        // Allow `echo $TOKEN | easer login` in scripts
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line
+    };
+    let token = Token::new(value);
+    if token.expose().is_empty() {
+        return Err(EaserError::Auth("No token entered".to_string()).into());
+    }
+    Ok(token)
+}
+
+/// `easer login`: prompt for a token, verify it and store it under a profile
+pub async fn login(args: LoginArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let given = args.api_url.or_else(gitee::api_url_from_env);
+    let api_url = normalize_api_url(given.as_deref().unwrap_or(DEFAULT_API_URL), API_PATH);
+    let token = read_token(&api_url)?;
+
+    let client = http::build_client(&HttpConfig::from(&args.http))?;
+    let login = verify_token(&client, &api_url, &token).await?;
+    info!("Authenticated to {} as {}", api_url, login);
+
+    let path = credentials_path()?;
+    let mut credentials = Credentials::load(&path)?;
+    credentials.profiles.insert(
+        args.profile.clone(),
+        Profile {
+            api_url: given.map(|_| api_url),
+            token: token.expose().to_string(),
+            login: Some(login),
+        },
+    );
+    credentials.save(&path)?;
+    info!("Saved profile '{}' to {}", args.profile, path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_credentials_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("easer").join(CREDENTIALS_FILE);
+        let mut credentials = Credentials::default();
+        credentials.profiles.insert(
+            "work".to_string(),
+            Profile {
+                api_url: Some("https://gitee.example.com".to_string()),
+                token: "t0k3n".to_string(),
+                login: Some("me".to_string()),
+            },
+        );
+
+        credentials.save(&path).unwrap();
+        let loaded = Credentials::load(&path).unwrap();
+
+        let work = &loaded.profiles["work"];
+        assert_eq!(work.token, "t0k3n");
+        assert_eq!(work.api_url.as_deref(), Some("https://gitee.example.com"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_malformed_credentials_exit_code() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CREDENTIALS_FILE);
+        std::fs::write(&path, "[profiles.work\ntoken = ").unwrap();
+
+        let e = EaserError::from(Credentials::load(&path).err().unwrap());
+
+        assert!(e.to_string().contains("Invalid credentials file"));
+        assert_eq!(e.exit_code(), 5);
+    }
+
+    #[test]
+    fn test_profile_refused_for_other_forge() {
+        let e = EaserError::from(load_profile("work", Forge::Github).err().unwrap());
+
+        assert!(e.to_string().contains("release on GitHub"));
+        assert_eq!(e.exit_code(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_verify_token() {
+        let mut server = Server::new_async().await;
+        let ok = server
+            .mock("GET", "/api/v5/user")
+            .match_header("Authorization", "token good")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": 1, "login": "me"}"#)
+            .create_async()
+            .await;
+        let rejected = server
+            .mock("GET", "/api/v5/user")
+            .match_header("Authorization", "token bad")
+            .with_status(401)
+            .with_body(r#"{"message": "401 Unauthorized: Access token bad is expired"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let client = reqwest::Client::new();
+
+        let login = verify_token(&client, &server.url(), &Token::new("good")).await;
+        let err = verify_token(&client, &server.url(), &Token::new("bad"))
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(login.unwrap(), "me");
+        assert!(err.contains("401") && !err.contains("token bad"));
+        let kind = EaserError::from(
+            verify_token(&client, &server.url(), &Token::new("bad"))
+                .await
+                .unwrap_err(),
+        )
+        .kind();
+        assert_eq!(kind, easer::ErrorKind::Auth);
+        ok.assert_async().await;
+        rejected.assert_async().await;
+    }
+}
@@ -0,0 +1,1067 @@
+use crate::artifact::{self, ArtifactSpec, LoadedArtifact};
+use crate::backend::{self, ApiError, ClientConfig, Forge, Release, ReleaseBackend, RemoteRelease};
+use crate::checksum::{self, ArtifactDigest};
+use crate::conventional::{default_sections, ChangelogGenerator, Section};
+use crate::error::{EaserError, ErrorKind};
+use crate::http::HttpConfig;
+use crate::messages::Messages;
+use crate::output::{AssetReport, ReleaseReport, UploadStatus};
+use crate::preflight::run_preflight;
+use crate::remote;
+use crate::sign::{self, SignMethod};
+use crate::token::{self, Token};
+use semver::Version;
+use std::error::Error;
+use std::fmt::Write;
+use std::path::Path;
+use tempfile::TempDir;
+use tokio::fs;
+use tracing::{error, field, info, instrument, warn, Span};
+
+/// The release and its uploads, computed without touching the API
+struct ReleasePlan {
+    release: Release,
+    artifacts: Vec<LoadedArtifact>,
+    /// Generated files (manifest, signatures) are staged in a private
+    /// directory of their own, removed when the plan is dropped after upload
+    staging: TempDir,
+}
+
+/// A repository to publish to. The token is resolved when connecting, in
+/// order, from `token`, the `token_env` variable, `token_file` and the git
+/// credential helper for the forge's web host
+#[derive(Debug, Clone)]
+pub struct ReleaseTarget {
+    pub forge: Forge,
+    pub owner: String,
+    pub repo: String,
+    /// Default: the forge's public instance
+    pub api_url: Option<String>,
+    pub token: Option<Token>,
+    pub token_env: String,
+    pub token_file: Option<String>,
+}
+
+impl ReleaseTarget {
+    /// A target on the forge's public instance, with its usual token variable
+    pub fn new(forge: Forge, owner: impl Into<String>, repo: impl Into<String>) -> Self {
+        ReleaseTarget {
+            forge,
+            owner: owner.into(),
+            repo: repo.into(),
+            api_url: None,
+            token: None,
+            token_env: forge.token_env().to_string(),
+            token_file: None,
+        }
+    }
+
+    /// e.g. `Gitee owner/repo`
+    pub fn label(&self) -> String {
+        format!("{} {}/{}", self.forge.display_name(), self.owner, self.repo)
+    }
+}
+
+/// A target with its backend ready to use
+struct Target {
+    label: String,
+    backend: Box<dyn ReleaseBackend>,
+    token: Token,
+}
+
+/// Build the backend with the resolved token, or an empty one when it will not
+/// be used. The credential helper is keyed by the forge's web host
+fn connect(
+    target: &ReleaseTarget,
+    http: &HttpConfig,
+    repo_path: &str,
+    needs_token: bool,
+) -> Result<Target, Box<dyn Error + Send + Sync>> {
+    let config = ClientConfig {
+        forge: target.forge,
+        api_url: target.api_url.clone(),
+        owner: target.owner.clone(),
+        repo: target.repo.clone(),
+        token: Token::new(""),
+        http: http.clone(),
+    };
+    let anonymous = backend::connect(&config)?;
+    if !needs_token {
+        return Ok(Target {
+            label: target.label(),
+            backend: anonymous,
+            token: Token::new(""),
+        });
+    }
+    let token = token::resolve_token(
+        target.token.as_ref(),
+        target.token_file.as_deref(),
+        &target.token_env,
+        &anonymous.repo_web_url(),
+        repo_path,
+    )?;
+    Ok(Target {
+        label: target.label(),
+        backend: backend::connect(&ClientConfig {
+            token: token.clone(),
+            ..config
+        })?,
+        token,
+    })
+}
+
+/// Put a detached signature right after each artifact in the upload list
+async fn sign_artifacts(
+    artifacts: Vec<LoadedArtifact>,
+    method: SignMethod,
+    key: Option<&str>,
+    out_dir: &Path,
+    messages: &Messages<'_>,
+) -> Result<Vec<LoadedArtifact>, Box<dyn Error + Send + Sync>> {
+    let mut signed = Vec::with_capacity(artifacts.len() * 2);
+    for artifact in artifacts {
+        let sig_path =
+            sign::sign_file(method, key, &artifact.path, &artifact.filename, out_dir).await?;
+        info!("{}: {}", messages.signed, artifact.filename);
+        let signature = LoadedArtifact {
+            filename: format!("{}.{}", artifact.filename, method.extension()),
+            content: fs::read(&sig_path).await?,
+            path: sig_path,
+        };
+        signed.push(artifact);
+        signed.push(signature);
+    }
+    Ok(signed)
+}
+
+/// Outcome of publishing to one target
+struct Published {
+    release: RemoteRelease,
+    assets: Vec<AssetReport>,
+}
+
+/// The result entry for one target
+fn report(
+    target: &Target,
+    release: &Release,
+    result: Result<&Published, EaserError>,
+) -> ReleaseReport {
+    let (remote, assets, error) = match result {
+        Ok(published) => (Some(&published.release), published.assets.clone(), None),
+        Err(e) => (None, Vec::new(), Some(e)),
+    };
+    ReleaseReport {
+        target: target.label.clone(),
+        id: remote.and_then(|remote| remote.id),
+        html_url: remote.and_then(|remote| remote.html_url.clone()),
+        tag: release.tag_name.clone(),
+        name: release.name.clone(),
+        draft: release.draft,
+        prerelease: release.prerelease,
+        assets,
+        error_kind: error.as_ref().map(EaserError::kind),
+        error: error.map(|e| e.to_string()),
+    }
+}
+
+/// Create the release on one target and upload the artifacts. Upload failures
+/// are logged and counted, not fatal
+#[instrument(
+    name = "release",
+    skip_all,
+    fields(target = %target.label, tag = %plan.release.tag_name, release_id = field::Empty)
+)]
+async fn publish(
+    target: &Target,
+    plan: &ReleasePlan,
+    messages: &Messages<'_>,
+) -> Result<Published, Box<dyn Error + Send + Sync>> {
+    let backend = target.backend.as_ref();
+    info!(
+        "Sending request to {} API: {}",
+        backend.forge().display_name(),
+        backend.releases_url()
+    );
+
+    let created = match backend.create_release(&plan.release).await {
+        Ok(created) => created,
+        Err(e) => {
+            return Err(match e.downcast::<ApiError>() {
+                Ok(api_error) => {
+                    error!(
+                        status = api_error.status.as_str(),
+                        details = api_error.body.as_str(),
+                        "{}: {}",
+                        messages.failure,
+                        api_error.status
+                    );
+                    EaserError::new(
+                        ErrorKind::of_response(api_error.status, &api_error.body),
+                        format!("{}: {}", messages.api_error, api_error),
+                    )
+                    .into()
+                }
+                Err(e) => {
+                    error!(
+                        "{}: {}",
+                        messages.failure,
+                        target.token.redact(&e.to_string())
+                    );
+                    e
+                }
+            });
+        }
+    };
+
+    Span::current().record("release_id", created.id);
+    if let Some(url) = &created.html_url {
+        info!("{}: {}", messages.success, url);
+    } else {
+        info!("{}", messages.success);
+    }
+
+    let assets = upload_artifacts(target, &created, &plan.artifacts, messages).await;
+    Ok(Published {
+        release: created,
+        assets,
+    })
+}
+
+/// Upload the artifacts to a release one by one, logging and reporting each
+/// outcome
+async fn upload_artifacts(
+    target: &Target,
+    release: &RemoteRelease,
+    artifacts: &[LoadedArtifact],
+    messages: &Messages<'_>,
+) -> Vec<AssetReport> {
+    let mut reports = Vec::with_capacity(artifacts.len());
+    for artifact in artifacts {
+        reports.push(upload_artifact(target, release, artifact, messages).await);
+    }
+    reports
+}
+
+#[instrument(
+    name = "upload",
+    skip_all,
+    fields(release_id = release.id, filename = %artifact.filename, size = artifact.content.len())
+)]
+async fn upload_artifact(
+    target: &Target,
+    release: &RemoteRelease,
+    artifact: &LoadedArtifact,
+    messages: &Messages<'_>,
+) -> AssetReport {
+    let filename = artifact.filename.as_str();
+    info!("{}: {}", messages.upload_start, filename);
+    let mut report = AssetReport {
+        name: artifact.filename.clone(),
+        size: artifact.content.len(),
+        download_url: None,
+        status: UploadStatus::Uploaded,
+        error: None,
+    };
+    match target.backend.upload_asset(release, artifact).await {
+        Ok(asset) => {
+            info!("{}: {}", messages.upload_success, filename);
+            report.download_url = Some(asset.browser_download_url).filter(|url| !url.is_empty());
+        }
+        Err(e) => {
+            report.status = UploadStatus::Failed;
+            report.error = Some(target.token.redact(&e.to_string()));
+            match e.downcast_ref::<ApiError>() {
+                Some(api_error) => error!(
+                    status = api_error.status.as_str(),
+                    details = api_error.body.as_str(),
+                    filename = filename,
+                    "{}: {} - Status: {}, Details: {}",
+                    messages.upload_failure,
+                    filename,
+                    api_error.status,
+                    api_error.body
+                ),
+                None => error!(
+                    filename = filename,
+                    error = target.token.redact(&e.to_string()).as_str(),
+                    "{}: {}",
+                    messages.upload_failure,
+                    filename
+                ),
+            }
+        }
+    }
+    report
+}
+
+/// Delete the releases created so far, newest first, together with uploads
+/// kept outside the release and the tag if this run created it
+async fn roll_back(
+    published: &[(&Target, RemoteRelease, bool)],
+    tag_name: &str,
+    messages: &Messages<'_>,
+) {
+    for (target, release, created_tag) in published.iter().rev() {
+        warn!("{}: {}", messages.rollback, target.label);
+        let backend = target.backend.as_ref();
+        let mut results = vec![
+            backend.delete_release(release).await,
+            backend.delete_uploads(release).await,
+        ];
+        if *created_tag {
+            results.push(backend.delete_tag(tag_name).await);
+        }
+        for e in results.into_iter().filter_map(Result::err) {
+            error!(
+                "{}: {} - {}",
+                messages.rollback_failure,
+                target.label,
+                target.token.redact(&e.to_string())
+            );
+        }
+    }
+}
+
+/// Read every artifact into memory, skipping paths that are not readable files.
+/// The contents are kept for the whole run, since checksums, signatures and
+/// every target's upload read them, so peak memory is the total artifact
+/// size. Uploads are not streamed from disk
+async fn load_artifacts(artifact_paths: &[String], messages: &Messages<'_>) -> Vec<LoadedArtifact> {
+    let mut artifacts = Vec::new();
+    for artifact_path_str in artifact_paths {
+        let spec = ArtifactSpec::parse(artifact_path_str);
+        let artifact_path = spec.path.as_path();
+        if !artifact_path.is_file() {
+            warn!(
+                "Artifact path is not a file or does not exist, skipping: {}",
+                artifact_path_str
+            );
+            continue;
+        }
+
+        let filename = match spec.upload_name() {
+            Some(name) => name,
+            None => {
+                warn!(
+                    "Could not get filename for artifact, skipping: {}",
+                    artifact_path_str
+                );
+                continue;
+            }
+        };
+
+        match fs::read(&artifact_path).await {
+            Ok(content) => artifacts.push(LoadedArtifact {
+                filename,
+                content,
+                path: artifact_path.to_path_buf(),
+            }),
+            Err(e) => {
+                error!(
+                    "{}: {} - {}",
+                    messages.file_read_error, artifact_path_str, e
+                );
+            }
+        }
+    }
+    artifacts
+}
+
+/// What [`ReleaseRequest::publish`] did on each target
+#[derive(Debug, Clone)]
+pub struct ReleaseOutcome {
+    /// The release as sent to the forges, including generated notes
+    pub release: Release,
+    /// One entry per target that was attempted, in order
+    pub targets: Vec<ReleaseReport>,
+    /// Labels of the targets not attempted after an all-or-nothing failure
+    pub skipped: Vec<String>,
+    /// Targets that failed. With all-or-nothing, a failed upload counts
+    pub failed: usize,
+    /// Whether the releases already created were deleted again
+    pub rolled_back: bool,
+}
+
+/// A release to publish on one or more targets, built up with chained setters
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// use easer::{Forge, ReleaseRequest, ReleaseTarget};
+///
+/// let outcome = ReleaseRequest::new()
+///     .target(ReleaseTarget::new(Forge::Github, "acme", "tool"))
+///     .auto_gen_notes(true)
+///     .artifacts(vec!["dist/tool.tar.gz".to_string()])
+///     .publish()
+///     .await?;
+/// println!("{:?}", outcome.targets[0].html_url);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReleaseRequest {
+    targets: Vec<ReleaseTarget>,
+    repo_path: String,
+    tag_name: Option<String>,
+    name: Option<String>,
+    body: Option<String>,
+    target_commitish: Option<String>,
+    previous_tag: Option<String>,
+    tag_prefix: String,
+    auto_gen_notes: bool,
+    sections: Vec<Section>,
+    draft: bool,
+    prerelease: bool,
+    artifacts: Vec<String>,
+    checksums: bool,
+    sign: Option<SignMethod>,
+    sign_key: Option<String>,
+    preflight: bool,
+    all_or_nothing: bool,
+    lang: String,
+    http: HttpConfig,
+}
+
+impl Default for ReleaseRequest {
+    fn default() -> Self {
+        ReleaseRequest {
+            targets: Vec::new(),
+            repo_path: ".".to_string(),
+            tag_name: None,
+            name: None,
+            body: None,
+            target_commitish: None,
+            previous_tag: None,
+            tag_prefix: "v".to_string(),
+            auto_gen_notes: false,
+            sections: default_sections(),
+            draft: false,
+            prerelease: false,
+            artifacts: Vec::new(),
+            checksums: false,
+            sign: None,
+            sign_key: None,
+            preflight: true,
+            all_or_nothing: false,
+            lang: "zh-cn".to_string(),
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+impl ReleaseRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a repository to publish to
+    pub fn target(mut self, target: ReleaseTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    pub fn targets(mut self, targets: impl IntoIterator<Item = ReleaseTarget>) -> Self {
+        self.targets.extend(targets);
+        self
+    }
+
+    /// Local git repository the notes, version and target are taken from
+    pub fn repo_path(mut self, repo_path: impl Into<String>) -> Self {
+        self.repo_path = repo_path.into();
+        self
+    }
+
+    /// With generated notes, overrides the computed version
+    pub fn tag_name(mut self, tag_name: impl Into<String>) -> Self {
+        self.tag_name = Some(tag_name.into());
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Branch or commit the tag is created from. Default: the checked-out
+    /// branch, or the commit when HEAD is detached
+    pub fn target_commitish(mut self, target_commitish: impl Into<String>) -> Self {
+        self.target_commitish = Some(target_commitish.into());
+        self
+    }
+
+    /// Tag the generated notes start from. Default: the latest version tag
+    pub fn previous_tag(mut self, previous_tag: impl Into<String>) -> Self {
+        self.previous_tag = Some(previous_tag.into());
+        self
+    }
+
+    pub fn tag_prefix(mut self, tag_prefix: impl Into<String>) -> Self {
+        self.tag_prefix = tag_prefix.into();
+        self
+    }
+
+    /// Generate tag, name and body from the Conventional Commits since the
+    /// previous tag
+    pub fn auto_gen_notes(mut self, auto_gen_notes: bool) -> Self {
+        self.auto_gen_notes = auto_gen_notes;
+        self
+    }
+
+    pub fn sections(mut self, sections: Vec<Section>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub fn draft(mut self, draft: bool) -> Self {
+        self.draft = draft;
+        self
+    }
+
+    pub fn prerelease(mut self, prerelease: bool) -> Self {
+        self.prerelease = prerelease;
+        self
+    }
+
+    /// Files to upload, `path#name` renames the asset. Patterns are not expanded
+    pub fn artifacts(mut self, artifacts: Vec<String>) -> Self {
+        self.artifacts = artifacts;
+        self
+    }
+
+    /// Append a checksum table to the notes and upload a SHA256SUMS manifest
+    pub fn checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Upload a detached signature next to every artifact
+    pub fn sign(mut self, method: SignMethod, key: Option<String>) -> Self {
+        self.sign = Some(method);
+        self.sign_key = key;
+        self
+    }
+
+    /// Run the pre-flight checks before anything is created (on by default)
+    pub fn preflight(mut self, preflight: bool) -> Self {
+        self.preflight = preflight;
+        self
+    }
+
+    /// With several targets, stop at the first failure and delete the
+    /// releases already created
+    pub fn all_or_nothing(mut self, all_or_nothing: bool) -> Self {
+        self.all_or_nothing = all_or_nothing;
+        self
+    }
+
+    /// Language of log messages, e.g. `en-us` or `zh-cn`
+    pub fn lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = lang.into();
+        self
+    }
+
+    pub fn http(mut self, http: HttpConfig) -> Self {
+        self.http = http;
+        self
+    }
+
+    fn connect_all(&self, needs_token: bool) -> Result<Vec<Target>, Box<dyn Error + Send + Sync>> {
+        if self.targets.is_empty() {
+            return Err(EaserError::Config("No release target given".to_string()).into());
+        }
+        self.targets
+            .iter()
+            .map(|target| connect(target, &self.http, &self.repo_path, needs_token))
+            .collect()
+    }
+
+    /// The changelog generator for this release's repository and tags
+    fn changelog(&self, target_commitish: &str) -> ChangelogGenerator {
+        let mut changelog = ChangelogGenerator::new(&self.repo_path)
+            .target(target_commitish)
+            .tag_prefix(&self.tag_prefix)
+            .sections(self.sections.clone());
+        if let Some(tag) = &self.previous_tag {
+            changelog = changelog.previous_tag(tag);
+        }
+        if let Some(version) = &self.tag_name {
+            changelog = changelog.version(version);
+        }
+        changelog
+    }
+
+    /// Work out tag, name, body and artifacts for the release. Only local state
+    /// (the git repository and the artifact files) is read.
+    async fn plan(
+        &self,
+        commit_url_prefix: &str,
+        messages: &Messages<'_>,
+    ) -> Result<ReleasePlan, Box<dyn Error + Send + Sync>> {
+        let target_commitish = match &self.target_commitish {
+            Some(target) => target.clone(),
+            None => remote::current_target(&self.repo_path)?,
+        };
+        let tag_name: String;
+        let release_name: String;
+        let mut release_body: String;
+
+        if self.auto_gen_notes {
+            info!("Auto-generating release notes...");
+            let info = self
+                .changelog(&target_commitish)
+                .commit_url_prefix(commit_url_prefix)
+                .generate()?;
+            tag_name = info.tag_name;
+            release_name = info.name;
+            release_body = info.body;
+        } else {
+            tag_name = self.tag_name.clone().unwrap_or_default();
+            release_name = self.name.clone().unwrap_or_default();
+            release_body = self.body.clone().unwrap_or_default();
+            [&tag_name, &release_name, &release_body]
+                .iter()
+                .filter(|s| s.is_empty())
+                .for_each(|_| {
+                    error!("Tag name, release name, and body cannot be empty");
+                });
+        }
+
+        let tag_name_to_parse = tag_name
+            .strip_prefix(self.tag_prefix.as_str())
+            .unwrap_or(&tag_name);
+        if Version::parse(tag_name_to_parse).is_err() {
+            let err_msg = format!("{}: {}", messages.invalid_tag, tag_name);
+            error!("{}", err_msg);
+            return Err(EaserError::InvalidVersion(err_msg).into());
+        }
+
+        let mut artifacts = load_artifacts(&self.artifacts, messages).await;
+        let staging = tempfile::Builder::new().prefix("easer-").tempdir()?;
+
+        if self.checksums && !artifacts.is_empty() {
+            let digests: Vec<ArtifactDigest> = artifacts
+                .iter()
+                .map(|a| ArtifactDigest::compute(&a.filename, &a.content))
+                .collect();
+            if !release_body.is_empty() {
+                release_body.push_str("\n\n");
+            }
+            release_body.push_str(&checksum::markdown_table(&digests));
+
+            let manifest_path = checksum::write_manifest(staging.path(), &digests).await?;
+            info!(
+                "{}: {}",
+                messages.checksum_written,
+                manifest_path.to_string_lossy()
+            );
+            artifacts.push(LoadedArtifact {
+                filename: checksum::MANIFEST_NAME.to_string(),
+                content: checksum::sha256sums(&digests).into_bytes(),
+                path: manifest_path,
+            });
+        }
+
+        Ok(ReleasePlan {
+            release: Release {
+                tag_name,
+                target_commitish,
+                name: release_name,
+                body: release_body,
+                draft: self.draft,
+                prerelease: self.prerelease,
+            },
+            artifacts,
+            staging,
+        })
+    }
+
+    /// Pre-flight every target, so that nothing is created unless all of them
+    /// pass. With several targets the error keeps the kind they failed with
+    /// if it is the same everywhere
+    async fn preflight_all(
+        &self,
+        targets: &[Target],
+        release: &Release,
+        messages: &Messages<'_>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut failed = Vec::new();
+        for target in targets {
+            if targets.len() > 1 {
+                eprintln!("{}", target.label);
+            }
+            let result = run_preflight(
+                target.backend.as_ref(),
+                &release.tag_name,
+                &release.target_commitish,
+                &self.artifacts,
+                messages,
+            )
+            .await;
+            if let Err(e) = result {
+                if targets.len() == 1 {
+                    return Err(e);
+                }
+                failed.push((target.label.as_str(), ErrorKind::of(e.as_ref())));
+            }
+        }
+        if !failed.is_empty() {
+            let labels: Vec<&str> = failed.iter().map(|(label, _)| *label).collect();
+            return Err(EaserError::new(
+                ErrorKind::common(failed.iter().map(|(_, kind)| *kind)),
+                format!("{}: {}", messages.preflight_failed, labels.join(", ")),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Run the pre-flight checks on their own, printing the checklist to stderr
+    pub async fn check(&self) -> Result<(), EaserError> {
+        let messages = Messages::new(&self.lang);
+        let targets = self.connect_all(true)?;
+
+        let plan = self
+            .plan(&targets[0].backend.commit_url_prefix(), &messages)
+            .await?;
+        Ok(self
+            .preflight_all(&targets, &plan.release, &messages)
+            .await?)
+    }
+
+    /// The API calls [`publish`](Self::publish) would make on each target,
+    /// with the token redacted. Nothing is sent and no token is needed
+    pub async fn dry_run(&self) -> Result<String, EaserError> {
+        let messages = Messages::new(&self.lang);
+        let targets = self.connect_all(false)?;
+        let plan = self
+            .plan(&targets[0].backend.commit_url_prefix(), &messages)
+            .await?;
+
+        Ok(self.render_dry_run(&targets, &plan)?)
+    }
+
+    fn render_dry_run(
+        &self,
+        targets: &[Target],
+        plan: &ReleasePlan,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut out = String::new();
+        for (i, target) in targets.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            let backend = target.backend.as_ref();
+            writeln!(out, "POST {}", backend.releases_url())?;
+            writeln!(out, "Authorization: {}", backend.authorization("***"))?;
+            writeln!(out, "{}", serde_json::to_string_pretty(&plan.release)?)?;
+            if plan.artifacts.is_empty() {
+                continue;
+            }
+            writeln!(out)?;
+            writeln!(out, "POST {}", backend.assets_url("{release_id}"))?;
+            for artifact in &plan.artifacts {
+                writeln!(
+                    out,
+                    "  {}  {} bytes  {}",
+                    artifact.filename,
+                    artifact.content.len(),
+                    artifact::content_type(&artifact.filename, &artifact.content)
+                )?;
+                if let Some(method) = self.sign {
+                    writeln!(
+                        out,
+                        "  {}.{}  (signature, created at upload time)",
+                        artifact.filename,
+                        method.extension()
+                    )?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Create the release on every target and upload the artifacts. Errors
+    /// before anything is created are returned, failures on a target are
+    /// reported in the outcome
+    pub async fn publish(&self) -> Result<ReleaseOutcome, EaserError> {
+        let messages = Messages::new(&self.lang);
+        let targets = self.connect_all(true)?;
+
+        // Commit links in the notes point at the first target
+        let mut plan = self
+            .plan(&targets[0].backend.commit_url_prefix(), &messages)
+            .await?;
+
+        if self.preflight {
+            self.preflight_all(&targets, &plan.release, &messages)
+                .await?;
+        }
+
+        if let Some(method) = self.sign {
+            plan.artifacts = sign_artifacts(
+                plan.artifacts,
+                method,
+                self.sign_key.as_deref(),
+                plan.staging.path(),
+                &messages,
+            )
+            .await?;
+        }
+
+        // With all-or-nothing a failed upload fails the target as well, and the
+        // first failure stops the run so that fewer releases need rolling back
+        let all_or_nothing = self.all_or_nothing && targets.len() > 1;
+        let mut published = Vec::new();
+        let mut reports = Vec::new();
+        let mut failed = 0;
+        for target in &targets {
+            // Only a tag this run creates is deleted again on roll back; when
+            // its existence cannot be told, the tag is left alone
+            let creates_tag = all_or_nothing
+                && matches!(
+                    target.backend.tag_exists(&plan.release.tag_name).await,
+                    Ok(false)
+                );
+            let result = publish(target, &plan, &messages).await;
+            let ok = match &result {
+                Ok(p) => {
+                    !(all_or_nothing
+                        && p.assets
+                            .iter()
+                            .any(|asset| asset.status == UploadStatus::Failed))
+                }
+                Err(_) => false,
+            };
+            if !ok {
+                failed += 1;
+            }
+            if let Ok(p) = result.as_ref() {
+                published.push((target, p.release.clone(), creates_tag));
+            }
+            reports.push(report(
+                target,
+                &plan.release,
+                result.as_ref().map_err(|e| {
+                    EaserError::new(
+                        ErrorKind::of(e.as_ref()),
+                        target.token.redact(&e.to_string()),
+                    )
+                }),
+            ));
+            if !ok && all_or_nothing {
+                break;
+            }
+        }
+
+        let rolled_back = failed > 0 && all_or_nothing;
+        if rolled_back {
+            roll_back(&published, &plan.release.tag_name, &messages).await;
+        }
+        Ok(ReleaseOutcome {
+            skipped: targets
+                .iter()
+                .skip(reports.len())
+                .map(|target| target.label.clone())
+                .collect(),
+            release: plan.release,
+            targets: reports,
+            failed,
+            rolled_back,
+        })
+    }
+
+    /// Add the artifacts to the release of `tag` that already exists on every
+    /// target. A target without that release is reported with an error
+    pub async fn upload(&self, tag: &str) -> Result<Vec<ReleaseReport>, EaserError> {
+        let messages = Messages::new(&self.lang);
+        let targets = self.connect_all(true)?;
+        let artifacts = load_artifacts(&self.artifacts, &messages).await;
+
+        let mut reports = Vec::new();
+        for target in &targets {
+            let found = match target.backend.find_release_by_tag(tag).await {
+                Ok(Some(release)) => Ok(release),
+                Ok(None) => Err(EaserError::NotFound(format!(
+                    "{}: {}",
+                    messages.release_not_found, tag
+                ))),
+                Err(e) => Err(EaserError::new(
+                    ErrorKind::of(e.as_ref()),
+                    target.token.redact(&e.to_string()),
+                )),
+            };
+            let release = match found {
+                Ok(release) => release,
+                Err(e) => {
+                    error!("{}: {} - {}", messages.failure, target.label, e);
+                    let missing = Release {
+                        tag_name: tag.to_string(),
+                        ..Default::default()
+                    };
+                    reports.push(report(target, &missing, Err(e)));
+                    continue;
+                }
+            };
+            let assets = upload_artifacts(target, &release, &artifacts, &messages).await;
+            let existing = Release {
+                tag_name: tag.to_string(),
+                target_commitish: release.target_commitish.clone(),
+                name: release.name.clone(),
+                body: release.body.clone(),
+                draft: release.draft,
+                prerelease: release.prerelease,
+            };
+            reports.push(report(
+                target,
+                &existing,
+                Ok(&Published { release, assets }),
+            ));
+        }
+        Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_release_request_publish() {
+        let mut server = Server::new_async().await;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("tool.tar.gz");
+        std::fs::write(&file_path, "archive").unwrap();
+        let release_mock = server
+            .mock("POST", "/api/v5/repos/acme/tool/releases")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"tag_name": "v2.0.0", "target_commitish": "main", "prerelease": true}"#
+                    .to_string(),
+            ))
+            .with_status(201)
+            .with_body(r#"{"id": 3, "html_url": "https://gitee.com/acme/tool/releases/v2.0.0"}"#)
+            .create_async()
+            .await;
+        let upload_mock = server
+            .mock("POST", "/api/v5/repos/acme/tool/releases/3/attach_files")
+            .with_status(201)
+            .with_body(r#"{"name": "tool.tar.gz", "browser_download_url": "https://gitee.com/dl/tool.tar.gz"}"#)
+            .create_async()
+            .await;
+
+        let outcome = ReleaseRequest::new()
+            .target(ReleaseTarget {
+                api_url: Some(server.url()),
+                token: Some(Token::new("library_token")),
+                ..ReleaseTarget::new(Forge::Gitee, "acme", "tool")
+            })
+            .tag_name("v2.0.0")
+            .name("Tool 2.0")
+            .body("Notes")
+            .target_commitish("main")
+            .prerelease(true)
+            .preflight(false)
+            .artifacts(vec![file_path.to_str().unwrap().to_string()])
+            .publish()
+            .await
+            .unwrap();
+
+        release_mock.assert_async().await;
+        upload_mock.assert_async().await;
+        assert_eq!(outcome.failed, 0);
+        assert_eq!(outcome.release.body, "Notes");
+        let report = &outcome.targets[0];
+        assert_eq!(report.id, Some(3));
+        assert_eq!(
+            report.assets[0].download_url.as_deref(),
+            Some("https://gitee.com/dl/tool.tar.gz")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_release_request_needs_a_target() {
+        let err = ReleaseRequest::new().publish().await.unwrap_err();
+        assert!(err.to_string().contains("No release target"));
+    }
+
+    #[tokio::test]
+    async fn test_publish_can_be_spawned() {
+        // Only compiles while the publish future is Send
+        let request = ReleaseRequest::new();
+        let result = tokio::spawn(async move { request.publish().await })
+            .await
+            .unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Config);
+    }
+
+    #[tokio::test]
+    async fn test_upload_reports_each_asset() {
+        let mut server = Server::new_async().await;
+        let find_mock = server
+            .mock("GET", "/repos/acme/tool/releases/tags/v1.0.0")
+            .with_status(200)
+            .with_body(format!(
+                r#"{{"id": 9, "tag_name": "v1.0.0", "name": "v1.0.0", "upload_url": "{}/repos/acme/tool/releases/9/assets{{?name,label}}"}}"#,
+                server.url()
+            ))
+            .create_async()
+            .await;
+        let ok_mock = server
+            .mock("POST", "/repos/acme/tool/releases/9/assets")
+            .match_query(Matcher::UrlEncoded("name".into(), "tool.tar.gz".into()))
+            .with_status(201)
+            .with_body(r#"{"name": "tool.tar.gz", "browser_download_url": "https://dl.example.com/tool.tar.gz"}"#)
+            .create_async()
+            .await;
+        let failed_mock = server
+            .mock("POST", "/repos/acme/tool/releases/9/assets")
+            .match_query(Matcher::UrlEncoded("name".into(), "tool.zip".into()))
+            .with_status(500)
+            .with_body("report_upload_token rejected")
+            .create_async()
+            .await;
+
+        let dir = tempdir().unwrap();
+        let artifacts = ["tool.tar.gz", "tool.zip"].map(|name| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "archive").unwrap();
+            path.to_str().unwrap().to_string()
+        });
+        let reports = ReleaseRequest::new()
+            .target(ReleaseTarget {
+                api_url: Some(server.url()),
+                token: Some(Token::new("report_upload_token")),
+                ..ReleaseTarget::new(Forge::Github, "acme", "tool")
+            })
+            .lang("en-us")
+            .artifacts(artifacts.to_vec())
+            .upload("v1.0.0")
+            .await
+            .unwrap();
+
+        find_mock.assert_async().await;
+        ok_mock.assert_async().await;
+        failed_mock.assert_async().await;
+        let assets = &reports[0].assets;
+        assert_eq!(reports[0].id, Some(9));
+        assert_eq!(assets[0].status, UploadStatus::Uploaded);
+        assert_eq!(assets[0].size, 7);
+        assert_eq!(
+            assets[0].download_url.as_deref(),
+            Some("https://dl.example.com/tool.tar.gz")
+        );
+        assert_eq!(assets[1].status, UploadStatus::Failed);
+        assert_eq!(assets[1].download_url, None);
+        assert!(!assets[1]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("report_upload_token"));
+    }
+}
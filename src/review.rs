@@ -0,0 +1,239 @@
+use easer::conventional::{Bump, ChangelogGenerator, ReleaseInfo};
+use easer::error::EaserError;
+use easer::messages::Messages;
+use git2::Repository;
+use semver::Version;
+use std::error::Error;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Edited notes are kept in the repository's git directory, one file per tag,
+/// so that a failed or aborted release can be retried without editing again
+pub fn notes_dir(repo_path: &str) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    Ok(Repository::open(repo_path)?.path().join("easer"))
+}
+
+/// Saved notes for `tag`, e.g. `.git/easer/v1.2.0.md`
+pub fn notes_path(dir: &Path, tag: &str) -> PathBuf {
+    dir.join(format!("{}.md", tag.replace(['/', '\\'], "_")))
+}
+
+/// `$VISUAL`, then `$EDITOR`, then the platform's default editor. The variable
+/// may carry arguments, e.g. `code --wait`
+fn open_editor(path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let default = if cfg!(windows) { "notepad" } else { "vi" };
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| default.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(default);
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Cannot run editor {}: {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("Editor {} exited with {}", editor, status).into());
+    }
+    Ok(())
+}
+
+fn parse_bump(answer: &str) -> Option<Bump> {
+    match answer.trim().to_lowercase().as_str() {
+        "major" => Some(Bump::Major),
+        "minor" => Some(Bump::Minor),
+        "patch" => Some(Bump::Patch),
+        _ => None,
+    }
+}
+
+/// Use the notes a previous run saved for this tag, if any
+fn reuse_saved(
+    notes: &mut ReleaseInfo,
+    dir: &Path,
+    out: &mut impl Write,
+    messages: &Messages<'_>,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let path = notes_path(dir, &notes.tag_name);
+    if !path.is_file() {
+        return Ok(false);
+    }
+    notes.body = std::fs::read_to_string(&path)?;
+    writeln!(out, "{}: {}", messages.review_notes_reused, path.display())?;
+    Ok(true)
+}
+
+/// Show the notes and ask until they are accepted. `regenerate` gives the
+/// notes for another bump level, `edit` opens the saved notes file. An edited
+/// body survives a bump change and is saved under the tag it ends up with
+fn review_loop(
+    mut notes: ReleaseInfo,
+    dir: &Path,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    mut regenerate: impl FnMut(Bump) -> Result<ReleaseInfo, Box<dyn Error + Send + Sync>>,
+    mut edit: impl FnMut(&Path) -> Result<(), Box<dyn Error + Send + Sync>>,
+    messages: &Messages<'_>,
+) -> Result<ReleaseInfo, Box<dyn Error + Send + Sync>> {
+    let mut edited = reuse_saved(&mut notes, dir, out, messages)?;
+    loop {
+        writeln!(out, "{}: {}", messages.review_version, notes.tag_name)?;
+        writeln!(out, "{}: {}", messages.review_title, notes.name)?;
+        writeln!(out, "\n{}\n", notes.body.trim_end())?;
+        write!(out, "{} ", messages.review_prompt)?;
+        out.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Err(EaserError::Other(messages.review_aborted.to_string()).into());
+        }
+        match answer.trim() {
+            "a" => return Ok(notes),
+            "e" => {
+                let path = notes_path(dir, &notes.tag_name);
+                std::fs::create_dir_all(dir)?;
+                std::fs::write(&path, &notes.body)?;
+                edit(&path)?;
+                notes.body = std::fs::read_to_string(&path)?;
+                edited = true;
+                writeln!(out, "{}: {}", messages.review_notes_saved, path.display())?;
+            }
+            "b" => {
+                write!(out, "{} ", messages.review_bump_prompt)?;
+                out.flush()?;
+                let mut answer = String::new();
+                input.read_line(&mut answer)?;
+                let Some(bump) = parse_bump(&answer) else {
+                    continue;
+                };
+                let next = regenerate(bump)?;
+                notes.tag_name = next.tag_name;
+                notes.name = next.name;
+                if !reuse_saved(&mut notes, dir, out, messages)? {
+                    if edited {
+                        std::fs::create_dir_all(dir)?;
+                        std::fs::write(notes_path(dir, &notes.tag_name), &notes.body)?;
+                    } else {
+                        notes.body = next.body;
+                    }
+                }
+            }
+            "q" => return Err(EaserError::Other(messages.review_aborted.to_string()).into()),
+            _ => {}
+        }
+    }
+}
+
+/// `--interactive`: generate the notes, let the user review, edit or re-bump
+/// them on the terminal and return what was accepted
+pub fn review_notes(
+    generator: &ChangelogGenerator,
+    repo_path: &str,
+    tag_prefix: &str,
+    messages: &Messages<'_>,
+) -> Result<ReleaseInfo, Box<dyn Error + Send + Sync>> {
+    if !std::io::stdin().is_terminal() {
+        return Err(EaserError::Config("--interactive needs a terminal".to_string()).into());
+    }
+    let regenerate = |bump: Bump| -> Result<ReleaseInfo, Box<dyn Error + Send + Sync>> {
+        let next = generator.next_version()?;
+        let base = match &next.base_tag {
+            Some(tag) => Version::parse(tag.strip_prefix(tag_prefix).unwrap_or(tag))?,
+            None => Version::new(0, 0, 0),
+        };
+        let version = format!("{}{}", tag_prefix, bump.apply(&base));
+        Ok(generator.clone().version(version).generate()?)
+    };
+    // Prompts go to stderr, stdout is reserved for results
+    review_loop(
+        generator.generate()?,
+        &notes_dir(repo_path)?,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+        regenerate,
+        open_editor,
+        messages,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn notes(version: &str, body: &str) -> ReleaseInfo {
+        ReleaseInfo {
+            tag_name: format!("v{}", version),
+            name: format!("Release {}", version),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_edit_then_change_bump() {
+        let dir = tempdir().unwrap();
+        let messages = Messages::new("en-us");
+        let mut out = Vec::new();
+
+        let reviewed = review_loop(
+            notes("1.0.1", "- fix crash"),
+            dir.path(),
+            &mut "e\nb\nminor\na\n".as_bytes(),
+            &mut out,
+            |bump| {
+                assert_eq!(bump, Bump::Minor);
+                Ok(notes("1.1.0", "- fix crash"))
+            },
+            |path| Ok(std::fs::write(path, "- fix crash on start")?),
+            &messages,
+        )
+        .unwrap();
+
+        assert_eq!(reviewed.tag_name, "v1.1.0");
+        assert_eq!(reviewed.name, "Release 1.1.0");
+        assert_eq!(reviewed.body, "- fix crash on start");
+        let saved = std::fs::read_to_string(notes_path(dir.path(), "v1.1.0")).unwrap();
+        assert_eq!(saved, "- fix crash on start");
+        assert!(String::from_utf8(out).unwrap().contains("Version: v1.0.1"));
+    }
+
+    #[test]
+    fn test_saved_notes_are_reused() {
+        let dir = tempdir().unwrap();
+        std::fs::write(notes_path(dir.path(), "v1.0.1"), "- edited before").unwrap();
+
+        let reviewed = review_loop(
+            notes("1.0.1", "- generated"),
+            dir.path(),
+            &mut "a\n".as_bytes(),
+            &mut Vec::new(),
+            |_| unreachable!(),
+            |_| unreachable!(),
+            &Messages::new("en-us"),
+        )
+        .unwrap();
+
+        assert_eq!(reviewed.body, "- edited before");
+    }
+
+    #[test]
+    fn test_abort() {
+        let dir = tempdir().unwrap();
+        for input in ["q\n", ""] {
+            let err = review_loop(
+                notes("1.0.1", "- generated"),
+                dir.path(),
+                &mut input.as_bytes(),
+                &mut Vec::new(),
+                |_| unreachable!(),
+                |_| unreachable!(),
+                &Messages::new("en-us"),
+            )
+            .unwrap_err();
+            assert_eq!(err.to_string(), "Release aborted");
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::checksum::{self, ArtifactDigest};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::error::Error;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use tokio::fs;
+use tokio::process::Command;
+use tracing::{error, info};
+
+/// Tool used to produce detached signatures
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignMethod {
+    /// ASCII-armored GnuPG signature (`.asc`)
+    Gpg,
+    /// SSH signature via `ssh-keygen -Y sign` (`.sig`)
+    Ssh,
+    /// minisign signature (`.minisig`)
+    Minisign,
+}
+
+impl SignMethod {
+    pub const ALL: [SignMethod; 3] = [SignMethod::Gpg, SignMethod::Ssh, SignMethod::Minisign];
+
+    /// File extension appended to the signed file name
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SignMethod::Gpg => "asc",
+            SignMethod::Ssh => "sig",
+            SignMethod::Minisign => "minisig",
+        }
+    }
+
+    fn program(&self) -> &'static str {
+        match self {
+            SignMethod::Gpg => "gpg",
+            SignMethod::Ssh => "ssh-keygen",
+            SignMethod::Minisign => "minisign",
+        }
+    }
+
+    fn is_signature(name: &str) -> bool {
+        Self::ALL
+            .iter()
+            .any(|m| name.ends_with(&format!(".{}", m.extension())))
+    }
+}
+
+/// SSH signatures are bound to a namespace; `file` is the conventional one for files
+const SSH_NAMESPACE: &str = "file";
+
+/// Create a detached signature for `file` and write it to `out_dir/<name>.<ext>`
+pub async fn sign_file(
+    method: SignMethod,
+    key: Option<&str>,
+    file: &Path,
+    name: &str,
+    out_dir: &Path,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    fs::create_dir_all(out_dir).await?;
+    let out = out_dir.join(format!("{}.{}", name, method.extension()));
+    let mut cmd = Command::new(method.program());
+    match method {
+        SignMethod::Gpg => {
+            cmd.args(["--batch", "--yes", "--armor", "--detach-sign"]);
+            if let Some(key) = key {
+                cmd.args(["--local-user", key]);
+            }
+            cmd.arg("--output").arg(&out).arg(file);
+        }
+        SignMethod::Ssh => {
+            let key = key.ok_or("SSH signing requires --sign-key <private key file>")?;
+            // Sign stdin so the signature lands in `out` instead of next to the artifact
+            cmd.args(["-Y", "sign", "-n", SSH_NAMESPACE, "-f", key])
+                .stdin(std::fs::File::open(file)?)
+                .stdout(std::fs::File::create(&out)?);
+        }
+        SignMethod::Minisign => {
+            cmd.arg("-S");
+            if let Some(key) = key {
+                cmd.args(["-s", key]);
+            }
+            cmd.arg("-m").arg(file).arg("-x").arg(&out);
+        }
+    }
+
+    let status = cmd.status().await.map_err(|e| {
+        format!(
+            "Failed to run {} for {}: {}",
+            method.program(),
+            file.display(),
+            e
+        )
+    })?;
+    if !status.success() {
+        return Err(format!(
+            "{} exited with {} while signing {}",
+            method.program(),
+            status,
+            file.display()
+        )
+        .into());
+    }
+    Ok(out)
+}
+
+/// Check a detached signature against `file`
+async fn verify_signature(
+    method: SignMethod,
+    key: Option<&str>,
+    identity: Option<&str>,
+    file: &Path,
+    signature: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut cmd = Command::new(method.program());
+    match method {
+        SignMethod::Gpg => {
+            cmd.args(["--batch", "--verify"]).arg(signature).arg(file);
+        }
+        SignMethod::Ssh => {
+            let signers = key.ok_or("SSH verification requires --key <allowed_signers file>")?;
+            let identity = identity.ok_or("SSH verification requires --identity <principal>")?;
+            cmd.args([
+                "-Y",
+                "verify",
+                "-n",
+                SSH_NAMESPACE,
+                "-f",
+                signers,
+                "-I",
+                identity,
+            ])
+            .arg("-s")
+            .arg(signature)
+            .stdin(std::fs::File::open(file)?);
+        }
+        SignMethod::Minisign => {
+            let public_key = key.ok_or("minisign verification requires --key <public key file>")?;
+            cmd.args(["-V", "-q", "-p", public_key, "-m"])
+                .arg(file)
+                .arg("-x")
+                .arg(signature);
+        }
+    }
+    let output = cmd
+        .stdout(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", method.program(), e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .to_string()
+            .into())
+    }
+}
+
+/// Split a `sha256sum` line into digest and file name. Text mode separates
+/// them with two spaces, binary mode with a space and `*`
+fn parse_manifest_line(line: &str) -> Option<(&str, &str)> {
+    let (digest, rest) = line.split_once(' ')?;
+    let name = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    Some((digest, name))
+}
+
+/// The manifest comes with the download, so its names must stay inside the
+/// release directory: one plain component, no `..` or absolute path
+fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+/// Verify every asset in a downloaded release directory offline: digests from
+/// the SHA256SUMS manifest and any detached signatures found next to the files
+pub async fn verify_release_dir(
+    dir: &Path,
+    method: Option<SignMethod>,
+    key: Option<&str>,
+    identity: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut names = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+
+    let mut failures = 0;
+
+    let manifest_path = dir.join(checksum::MANIFEST_NAME);
+    if manifest_path.is_file() {
+        let manifest = fs::read_to_string(&manifest_path).await?;
+        for line in manifest.lines().filter(|l| !l.trim().is_empty()) {
+            let Some((expected, name)) = parse_manifest_line(line) else {
+                error!("Malformed {} line: {}", checksum::MANIFEST_NAME, line);
+                failures += 1;
+                continue;
+            };
+            if !is_plain_file_name(name) {
+                error!("[FAIL] sha256 {}: not a file in {}", name, dir.display());
+                failures += 1;
+                continue;
+            }
+            match fs::read(dir.join(name)).await {
+                Ok(content) if ArtifactDigest::compute(name, &content).sha256 == expected => {
+                    info!("[OK] sha256 {}", name);
+                }
+                Ok(_) => {
+                    error!("[FAIL] sha256 {}: digest mismatch", name);
+                    failures += 1;
+                }
+                Err(e) => {
+                    error!("[FAIL] sha256 {}: {}", name, e);
+                    failures += 1;
+                }
+            }
+        }
+    } else {
+        info!(
+            "No {} found, skipping digest check",
+            checksum::MANIFEST_NAME
+        );
+    }
+
+    let methods: Vec<SignMethod> = match method {
+        Some(m) => vec![m],
+        None => SignMethod::ALL.to_vec(),
+    };
+    for name in names.iter().filter(|n| !SignMethod::is_signature(n)) {
+        let mut signed = false;
+        for m in &methods {
+            let signature = dir.join(format!("{}.{}", name, m.extension()));
+            if !signature.is_file() {
+                continue;
+            }
+            signed = true;
+            match verify_signature(*m, key, identity, &dir.join(name), &signature).await {
+                Ok(()) => info!("[OK] {:?} signature {}", m, name),
+                Err(e) => {
+                    error!("[FAIL] {:?} signature {}: {}", m, name, e);
+                    failures += 1;
+                }
+            }
+        }
+        if !signed {
+            error!("[FAIL] {}: no signature found", name);
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!("Verification failed for {} check(s)", failures).into());
+    }
+    info!("All assets in {} verified", dir.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_signature() {
+        assert!(SignMethod::is_signature("app.tar.gz.asc"));
+        assert!(SignMethod::is_signature("SHA256SUMS.minisig"));
+        assert!(!SignMethod::is_signature("app.tar.gz"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_digest_mismatch() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("app.txt"))
+            .unwrap()
+            .write_all(b"tampered")
+            .unwrap();
+        let digests = vec![ArtifactDigest::compute("app.txt", b"original")];
+        checksum::write_manifest(dir.path(), &digests)
+            .await
+            .unwrap();
+
+        let result = verify_release_dir(dir.path(), None, None, None).await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_manifest_names() {
+        assert_eq!(
+            parse_manifest_line("abc  app.tar.gz"),
+            Some(("abc", "app.tar.gz"))
+        );
+        assert_eq!(
+            parse_manifest_line("abc *app.tar.gz"),
+            Some(("abc", "app.tar.gz"))
+        );
+        assert_eq!(parse_manifest_line("abc app.tar.gz"), None);
+
+        assert!(is_plain_file_name("app.tar.gz"));
+        for name in ["../secret", "/etc/passwd", "sub/app.tar.gz", "..", "./app"] {
+            assert!(!is_plain_file_name(name), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::{EaserError, ErrorKind};
+use git2::{Config, CredentialHelper, Repository};
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use tracing::info;
+
+const REDACTED: &str = "***";
+
+/// An access token that never prints its value through `Debug` or `Display`
+#[derive(Clone, PartialEq, Eq)]
+pub struct Token(String);
+
+impl Token {
+    pub fn new(value: impl Into<String>) -> Self {
+        Token(value.into().trim().to_string())
+    }
+
+    /// The raw token, only for building request headers
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Replace every occurrence of the token in `text`, e.g. an echoed API error body
+    pub fn redact(&self, text: &str) -> String {
+        if self.0.is_empty() {
+            text.to_string()
+        } else {
+            text.replace(&self.0, REDACTED)
+        }
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token({})", REDACTED)
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl FromStr for Token {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Token::new(s))
+    }
+}
+
+/// Resolve the access token, in order, from `--token`, the forge's token
+/// variable (`token_env`), `--token-file` and finally the git credential
+/// helper for the forge's web host
+pub fn resolve_token(
+    token: Option<&Token>,
+    token_file: Option<&str>,
+    token_env: &str,
+    web_url: &str,
+    repo_path: &str,
+) -> Result<Token, Box<dyn Error + Send + Sync>> {
+    if let Some(token) = token.filter(|t| !t.expose().is_empty()) {
+        return Ok(token.clone());
+    }
+    if let Ok(value) = std::env::var(token_env) {
+        if !value.trim().is_empty() {
+            info!("Using access token from {}", token_env);
+            return Ok(Token::new(value));
+        }
+    }
+    if let Some(path) = token_file {
+        let value = std::fs::read_to_string(path).map_err(|e| {
+            EaserError::Config(format!("Failed to read token file {}: {}", path, e))
+        })?;
+        if value.trim().is_empty() {
+            return Err(EaserError::Auth(format!("Token file {} is empty", path)).into());
+        }
+        info!("Using access token from {}", path);
+        return Ok(Token::new(value));
+    }
+    let config = match Repository::open(repo_path) {
+        Ok(repo) => repo.config()?,
+        Err(_) => Config::open_default()?,
+    };
+    if let Some((_, password)) = CredentialHelper::new(web_url).config(&config).execute() {
+        info!("Using access token from git credential helper");
+        return Ok(Token::new(password));
+    }
+    Err(EaserError::new(
+        ErrorKind::Auth,
+        format!(
+            "No access token found: pass --token, set {}, use --token-file or configure a git credential helper for {}",
+            token_env, web_url
+        ),
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_is_redacted() {
+        let token = Token::new("s3cr3t\n");
+        assert_eq!(token.expose(), "s3cr3t");
+        assert_eq!(format!("{:?} {}", token, token), "Token(***) ***");
+        assert_eq!(
+            token.redact("invalid access_token=s3cr3t"),
+            "invalid access_token=***"
+        );
+    }
+
+    #[test]
+    fn test_explicit_token_wins() {
+        let token = Token::new("from-flag");
+        let resolved = resolve_token(
+            Some(&token),
+            Some("/nonexistent"),
+            "GITEE_TOKEN",
+            "https://gitee.com",
+            ".",
+        );
+        assert_eq!(resolved.unwrap(), token);
+    }
+}